@@ -0,0 +1,288 @@
+use crate::source::BuildSource;
+use crate::util::hash;
+use eyre::{ContextCompat, Result, WrapErr};
+use log::warn;
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// A download cache shared by all projects.
+///
+/// Files are stored in `<kind>/<key>/<file>`, where the key is the sha512 of the file
+/// when it is known, or the sha256 of the url otherwise.
+#[derive(Debug, Clone)]
+pub struct Cache {
+    pub path: PathBuf,
+}
+
+#[derive(Debug, Clone)]
+pub struct CacheEntry {
+    pub path: PathBuf,
+    pub file: String,
+    pub size: u64,
+    pub last_used: SystemTime,
+}
+
+impl Cache {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    /// Opens the cache in `NITER_CACHE_DIR`, or in the user's cache directory.
+    pub fn open() -> Result<Self> {
+        let path = match std::env::var_os("NITER_CACHE_DIR") {
+            Some(path) => PathBuf::from(path),
+            None => dirs::cache_dir()
+                .wrap_err("could not find the user's cache directory")?
+                .join("niterpack"),
+        };
+
+        Ok(Cache::new(path))
+    }
+
+    fn entry_dir(&self, source: &BuildSource) -> Result<PathBuf> {
+        source.check()?;
+
+        Ok(match &source.sha512 {
+            Some(sha512) => self.path.join("sha512").join(sha512),
+            None => self.path.join("url").join(hex::encode(Sha256::digest(
+                source.url.as_deref().unwrap_or_default().as_bytes(),
+            ))),
+        })
+    }
+
+    /// Returns the path of a cached file, if it exists and matches the known hashes of
+    /// the source.
+    ///
+    /// Files that don't match are removed from the cache.
+    pub fn get(&self, source: &BuildSource) -> Result<Option<PathBuf>> {
+        let path = self.entry_dir(source)?.join(&source.file);
+        if !path.is_file() {
+            return Ok(None);
+        }
+
+        let mismatch = hash::mismatch(&path, source.sha512.as_deref(), source.sha1.as_deref())
+            .wrap_err("failed to hash cached file")?;
+        if let Some((algorithm, _, _)) = mismatch {
+            warn!(
+                "cached `{}` doesn't match its {}, removing it",
+                source.file, algorithm
+            );
+            fs::remove_file(&path).wrap_err("failed to remove corrupted cache entry")?;
+            return Ok(None);
+        }
+
+        Ok(Some(path))
+    }
+
+    /// Returns the path of a cached file, storing it using `download` if it isn't cached yet.
+    ///
+    /// The result of `download` is returned if it was called. Files that failed to download
+    /// are kept as `.part` files, so that the download can be resumed.
+    pub fn get_or_insert<T, F>(
+        &self,
+        source: &BuildSource,
+        download: F,
+    ) -> Result<(PathBuf, Option<T>)>
+    where
+        F: FnOnce(&Path) -> Result<T>,
+    {
+        if let Some(path) = self.get(source)? {
+            touch(&path)?;
+            return Ok((path, None));
+        }
+
+        let dir = self.entry_dir(source)?;
+        fs::create_dir_all(&dir).wrap_err("failed to create cache directory")?;
+
+        let path = dir.join(&source.file);
+        let part = dir.join(format!("{}.part", &source.file));
+        let result = download(&part)?;
+        fs::rename(&part, &path).wrap_err("failed to move download into cache")?;
+
+        Ok((path, Some(result)))
+    }
+
+    pub fn entries(&self) -> Result<Vec<CacheEntry>> {
+        let mut entries = Vec::new();
+        for kind in ["sha512", "url"] {
+            let kind_dir = self.path.join(kind);
+            if !kind_dir.exists() {
+                continue;
+            }
+
+            for entry_dir in fs::read_dir(kind_dir)? {
+                for file in fs::read_dir(entry_dir?.path())? {
+                    let file = file?;
+                    let metadata = file.metadata()?;
+                    if !metadata.is_file() {
+                        continue;
+                    }
+
+                    entries.push(CacheEntry {
+                        path: file.path(),
+                        file: file.file_name().to_string_lossy().into_owned(),
+                        size: metadata.len(),
+                        last_used: metadata.modified()?,
+                    });
+                }
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Removes entries that haven't been used for `max_age`, returning the removed entries.
+    pub fn prune(&self, max_age: Duration) -> Result<Vec<CacheEntry>> {
+        let now = SystemTime::now();
+        let mut removed = Vec::new();
+        for entry in self.entries()? {
+            let age = now.duration_since(entry.last_used).unwrap_or_default();
+            if age < max_age {
+                continue;
+            }
+
+            let dir = entry.path.parent().map(Path::to_path_buf);
+            fs::remove_file(&entry.path)?;
+            if let Some(dir) = dir {
+                if fs::read_dir(&dir)?.next().is_none() {
+                    fs::remove_dir(dir)?;
+                }
+            }
+            removed.push(entry);
+        }
+        Ok(removed)
+    }
+
+    pub fn clean(&self) -> Result<()> {
+        if self.path.exists() {
+            fs::remove_dir_all(&self.path)?;
+        }
+        Ok(())
+    }
+}
+
+/// Installs a cached file by copying it, so that changes to the installed file don't
+/// reach the cache.
+pub fn install(from: &Path, to: &Path) -> io::Result<()> {
+    // Files installed by older builds can be hard links to the cache, they are removed
+    // first so that the cache isn't overwritten
+    if to.exists() {
+        fs::remove_file(to)?;
+    }
+
+    fs::copy(from, to).map(|_| ())
+}
+
+fn touch(path: &Path) -> Result<()> {
+    File::options()
+        .write(true)
+        .open(path)?
+        .set_modified(SystemTime::now())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ContentKind;
+    use sha2::Sha512;
+    use std::env;
+
+    fn cache(name: &str) -> Cache {
+        let path = env::temp_dir().join(format!("niterpack-cache-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&path);
+        Cache::new(path)
+    }
+
+    fn source(file: &str, contents: &str) -> BuildSource {
+        BuildSource {
+            name: "mod".to_string(),
+            project_id: None,
+            version_id: None,
+            version_number: None,
+            url: Some(format!("https://cdn.example.com/{}", file)),
+            path: None,
+            file: file.to_string(),
+            sha512: Some(hex::encode(Sha512::digest(contents))),
+            sha1: None,
+            size: None,
+            kind: ContentKind::Mod,
+            dependencies: Vec::new(),
+        }
+    }
+
+    fn insert(cache: &Cache, source: &BuildSource, contents: &str) -> (PathBuf, Option<()>) {
+        cache
+            .get_or_insert(source, |part| Ok(fs::write(part, contents)?))
+            .unwrap()
+    }
+
+    #[test]
+    fn stores_and_reuses_files() {
+        let cache = cache("reuse");
+        let source = source("mod.jar", "mod");
+
+        let (path, downloaded) = insert(&cache, &source, "mod");
+        let (cached, reused) = insert(&cache, &source, "other");
+        let contents = fs::read_to_string(&cached).unwrap();
+        cache.clean().unwrap();
+
+        assert!(downloaded.is_some());
+        assert!(reused.is_none());
+        assert_eq!(path, cached);
+        assert_eq!(contents, "mod");
+    }
+
+    #[test]
+    fn removes_corrupted_files() {
+        let cache = cache("corrupted");
+        let source = source("mod.jar", "mod");
+
+        let (path, _) = insert(&cache, &source, "mod");
+        fs::write(&path, "tampered").unwrap();
+        let found = cache.get(&source).unwrap();
+        let exists = path.exists();
+        cache.clean().unwrap();
+
+        assert_eq!(found, None);
+        assert!(!exists);
+    }
+
+    #[test]
+    fn rejects_unsafe_paths() {
+        let cache = cache("unsafe");
+
+        for file in ["../mod.jar", "..", "mods/mod.jar", "mods\\mod.jar", ""] {
+            assert!(cache.get(&source(file, "mod")).is_err(), "{}", file);
+        }
+
+        let traversal = BuildSource {
+            sha512: Some("../../secret".to_string()),
+            ..source("mod.jar", "mod")
+        };
+        let uppercase = BuildSource {
+            sha512: Some("AB".repeat(64)),
+            ..source("mod.jar", "mod")
+        };
+        assert!(cache.get(&traversal).is_err());
+        assert!(cache.get(&uppercase).is_err());
+        assert!(!cache.path.exists());
+    }
+
+    #[test]
+    fn installs_copies() {
+        let cache = cache("install");
+        let source = source("mod.jar", "mod");
+
+        let (path, _) = insert(&cache, &source, "mod");
+        let installed = cache.path.join("installed.jar");
+        install(&path, &installed).unwrap();
+        fs::write(&installed, "tampered").unwrap();
+        let contents = fs::read_to_string(&path).unwrap();
+        cache.clean().unwrap();
+
+        assert_eq!(contents, "mod");
+    }
+}
@@ -1,74 +1,361 @@
 use crate::toml::JoinToml;
-use crate::util::modrinth::{self, error::NotFound};
-use crate::Mod;
-use crate::Source;
-use eyre::{ensure, ContextCompat, WrapErr};
+use crate::util::github::GitHubClient;
+use crate::util::hash;
+use crate::util::modrinth::{self, error::NotFound, ModrinthClient, ProjectType};
+use crate::{ops, source, ContentKind, Lockfile, Manifest, Mod, Project, Source};
+use eyre::{bail, ensure, ContextCompat, WrapErr};
 use log::info;
 use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Number of close matches suggested when a project isn't found.
+const SUGGESTIONS: usize = 3;
 
 #[derive(clap::Args)]
 pub struct AddArgs {
-    /// Reference to a project to add as a mod
+    /// Reference to a project to add as a mod, resource pack, shader pack, datapack or plugin
     ///
     /// You can reference a project using a slug, or an id.
-    #[arg(id = "MOD")]
-    mod_name: String,
+    #[arg(id = "MOD", required_unless_present_any = ["file", "path", "github"])]
+    mod_name: Option<String>,
 
     /// Reference to the project's version
     ///
     /// You can reference a version using a number, or an id.
     #[arg(id = "VERSION", short = 'v', long = "version")]
     version_name: Option<String>,
-}
 
-impl AddArgs {
-    pub fn mod_data(&self) -> eyre::Result<Mod> {
-        let path = env::current_dir().unwrap();
+    /// Add a mod from a jar file instead
+    ///
+    /// The file is looked up on Modrinth by its hash. If it isn't found there, it is
+    /// copied into the `files` directory of the modpack.
+    #[arg(long, conflicts_with_all = ["MOD", "VERSION"])]
+    file: Option<PathBuf>,
+
+    /// Add a mod from a local file, without looking it up on Modrinth
+    ///
+    /// Files outside of the modpack are copied into its `files` directory.
+    #[arg(long, conflicts_with_all = ["MOD", "VERSION", "file"])]
+    path: Option<PathBuf>,
+
+    /// Add a mod from the newest release of a GitHub repository instead
+    ///
+    /// The repository is referenced as `owner/name`, and the release is pinned by its tag.
+    #[arg(long, value_name = "REPO", conflicts_with_all = ["MOD", "VERSION", "file", "path"])]
+    github: Option<String>,
+
+    /// Kind of content of a local file or release asset
+    ///
+    /// Jar files are added as mods by default.
+    #[arg(long, value_enum, conflicts_with = "MOD")]
+    kind: Option<ContentKind>,
+
+    /// Glob pattern matching the name of the release asset to add
+    #[arg(
+        long,
+        value_name = "GLOB",
+        requires = "github",
+        default_value = "*.jar"
+    )]
+    asset: String,
 
-        let manifest = crate::toml::read_manifest(path.join_manifest_file())
-            .wrap_err("failed to read manifest file")?;
+    /// Add the mod even if it is incompatible with other mods
+    #[arg(short, long)]
+    force: bool,
+}
 
-        let project = modrinth::project(&self.mod_name)
+impl AddArgs {
+    pub fn resolve(
+        &self,
+        client: &ModrinthClient,
+        manifest: &Manifest,
+        mod_name: &str,
+    ) -> eyre::Result<(modrinth::Project, modrinth::Version)> {
+        let Some(project) = client
+            .project(mod_name)
             .not_found()
             .wrap_err("failed to fetch modrinth project")?
-            .wrap_err(format!("project `{}` not found", &self.mod_name))?;
+        else {
+            // Suggest close matches, the search failing shouldn't hide the original error
+            let suggestions: Vec<String> = ops::search(
+                client,
+                Some(manifest),
+                mod_name,
+                &ProjectType::Mod,
+                SUGGESTIONS,
+            )
+            .map(|hits| {
+                hits.into_iter()
+                    .map(|hit| format!("`{}`", hit.slug))
+                    .collect()
+            })
+            .unwrap_or_default();
 
-        ensure!(
-            project.project_type == modrinth::ProjectType::Mod,
-            "only `mod` project types are allowed"
-        );
+            if suggestions.is_empty() {
+                bail!("project `{}` not found", mod_name);
+            }
+            bail!(
+                "project `{}` not found, did you mean {}?",
+                mod_name,
+                suggestions.join(", ")
+            );
+        };
+
+        let kind = content_kind(&project)?;
 
         let version = match &self.version_name {
-            Some(version_name) => version_name.into(),
-            None => {
-                modrinth::project_versions(
+            Some(version_name) => {
+                source::modrinth_version(client, manifest, kind, &project.id, version_name)
+            }
+            None => client
+                .project_versions(
                     &project.id,
-                    manifest.loader.as_deref(),
+                    kind.loaders(manifest).as_deref(),
                     manifest.minecraft_version.as_deref(),
                 )
                 .wrap_err("failed to fetch project versions")?
-                .first()
-                .wrap_err("project doesn't have a valid version for this modpack")?
-                .clone()
-                .version_number
-            }
-        };
+                .into_iter()
+                .next()
+                .wrap_err("project doesn't have a valid version for this modpack"),
+        }?;
+
+        Ok((project, version))
+    }
+
+    /// Identifies a jar file on Modrinth, falling back to a local file source.
+    pub fn resolve_file(
+        &self,
+        client: &ModrinthClient,
+        path: &Path,
+        file: &Path,
+    ) -> eyre::Result<(Mod, Option<modrinth::Version>)> {
+        let hash = hash::sha512(file).wrap_err("failed to generate sha512")?;
+        let version = client
+            .version_file(&hash, "sha512")
+            .not_found()
+            .wrap_err("failed to fetch modrinth version")?;
+
+        if let Some(version) = version {
+            let project = client
+                .project(&version.project_id)
+                .wrap_err("failed to fetch modrinth project")?;
+            let kind = content_kind(&project)?;
+
+            // Version numbers aren't unique, pin the exact version the file belongs to
+            return Ok((modrinth_mod(&project, &version.id, kind), Some(version)));
+        }
+
+        info!("Mod was not found on Modrinth, adding it as a local file");
+        Ok((local_mod(path, file, self.kind)?, None))
+    }
+
+    /// Pins the asset of the newest release of a GitHub repository.
+    pub fn resolve_github(&self, repo: &str) -> eyre::Result<Mod> {
+        let client = GitHubClient::from_env().wrap_err("failed to create github client")?;
+        let release = client
+            .latest_release(repo)
+            .wrap_err("failed to fetch latest github release")?;
+        let asset = source::release_asset(&release, &self.asset)?;
+        info!(
+            "Found asset `{}` in release `{}`",
+            asset.name, release.tag_name
+        );
+
+        let name = repo
+            .rsplit_once('/')
+            .map_or(repo, |(_, name)| name)
+            .to_string();
+        let kind = file_kind(&asset.name, self.kind)?;
 
-        Ok(Mod::new(project.slug, None, Source::Modrinth { version }))
+        Ok(Mod::new(
+            name,
+            None,
+            None,
+            Source::GitHub {
+                repo: repo.to_string(),
+                tag: release.tag_name.clone(),
+                asset: self.asset.clone(),
+            },
+            kind,
+        ))
     }
 
     pub fn run(&self) -> eyre::Result<()> {
-        let mod_data = self.mod_data()?;
+        let path = env::current_dir().unwrap();
+
+        let client = ModrinthClient::from_env().wrap_err("failed to create modrinth client")?;
+        let mut modpack = Project::read(&path)?;
+        let (mod_data, version) = match (&self.file, &self.path, &self.github, &self.mod_name) {
+            (Some(file), _, _, _) => self.resolve_file(&client, &path, file)?,
+            (None, Some(file), _, _) => (local_mod(&path, file, self.kind)?, None),
+            (None, None, Some(repo), _) => (self.resolve_github(repo)?, None),
+            (None, None, None, Some(mod_name)) => {
+                let (project, version) = self.resolve(&client, &modpack.manifest, mod_name)?;
+                let kind = content_kind(&project)?;
+                (
+                    modrinth_mod(&project, &version.version_number, kind),
+                    Some(version),
+                )
+            }
+            (None, None, None, None) => unreachable!("clap requires a mod or a file"),
+        };
+
+        // Adding a mod again updates it, but a different mod would overwrite its file
+        if let Some(existing) = modpack
+            .mods
+            .iter()
+            .find(|existing| existing.name == mod_data.name && existing.kind == mod_data.kind)
+        {
+            ensure!(
+                same_origin(&existing.source, &mod_data.source),
+                "{} `{}` already exists with a different source, remove it first",
+                mod_data.kind.as_str(),
+                mod_data.name
+            );
+        }
+        modpack
+            .mods
+            .retain(|existing| existing.name != mod_data.name || existing.kind != mod_data.kind);
+        modpack.mods.push(mod_data.clone());
+
+        let dependencies = match &version {
+            Some(version) => {
+                ops::required_dependencies(&client, &modpack.manifest, &modpack.mods, version)
+                    .wrap_err("failed to resolve dependencies")?
+            }
+            None => Vec::new(),
+        };
+        modpack.mods.extend(dependencies.iter().cloned());
+
+        // Check the resulting modpack for incompatible mods before writing anything
+        let lock_path = path.join_lock_file();
+        let previous = if lock_path.exists() {
+            Some(Lockfile::read(&lock_path).wrap_err("failed to read lock file")?)
+        } else {
+            None
+        };
+        let lockfile = Lockfile::resolve(&client, &modpack, previous.as_ref())?;
+        ops::check_incompatibilities(&lockfile.sources(), self.force)?;
+
+        let file = self.file.as_ref().or(self.path.as_ref());
+        if let (Some(file), Source::Path { path: target }) = (file, &mod_data.source) {
+            let relative = target;
+            let target = path.join(target);
+            if !target.exists() {
+                fs::create_dir_all(path.join_files_dir())
+                    .wrap_err("failed to create files directory")?;
+                fs::copy(file, &target).wrap_err("failed to copy file into modpack")?;
+            } else if !same_file(file, &target)? {
+                ensure!(
+                    hash::sha512(file)? == hash::sha512(&target)?,
+                    "`{}` already exists with a different content, rename the file or remove the existing one",
+                    relative
+                );
+            }
+        }
 
-        crate::toml::write_mod(
-            env::current_dir()
-                .unwrap()
-                .join_mods_dir()
-                .join_mod_file(&mod_data.name),
-            mod_data.clone(),
-        )?;
+        for mod_data in std::iter::once(mod_data).chain(dependencies) {
+            let dir = path.join_content_dir(mod_data.kind);
+            if !dir.exists() {
+                fs::create_dir(&dir).wrap_err(format!(
+                    "failed to create {} directory",
+                    mod_data.kind.dir_name()
+                ))?;
+            }
+            crate::toml::write_mod(dir.join_mod_file(&mod_data.name), mod_data.clone())?;
 
-        info!("Added mod `{}` to modpack", mod_data.name);
+            info!(
+                "Added {} `{}` to modpack",
+                mod_data.kind.as_str(),
+                mod_data.name
+            );
+        }
         Ok(())
     }
 }
+
+/// Whether two sources point to the same mod, possibly to different versions of it.
+fn same_origin(a: &Source, b: &Source) -> bool {
+    match (a, b) {
+        (Source::Modrinth { .. }, Source::Modrinth { .. }) => true,
+        (Source::Download { url: a, .. }, Source::Download { url: b, .. }) => a == b,
+        (Source::Path { path: a }, Source::Path { path: b }) => a == b,
+        (Source::GitHub { repo: a, .. }, Source::GitHub { repo: b, .. }) => a == b,
+        _ => false,
+    }
+}
+
+fn same_file(a: &Path, b: &Path) -> eyre::Result<bool> {
+    Ok(a.canonicalize()? == b.canonicalize()?)
+}
+
+/// Creates a local file source for a file.
+///
+/// Files already inside the modpack are referenced in place, the others are referenced
+/// from the `files` directory they are copied into.
+fn local_mod(path: &Path, file: &Path, kind: Option<ContentKind>) -> eyre::Result<Mod> {
+    ensure!(file.is_file(), "file `{}` not found", file.display());
+
+    let file_name = file
+        .file_name()
+        .and_then(|name| name.to_str())
+        .wrap_err("invalid file name")?;
+    let name = file
+        .file_stem()
+        .and_then(|name| name.to_str())
+        .wrap_err("invalid file name")?
+        .to_string();
+
+    let relative = match (file.canonicalize(), path.canonicalize()) {
+        (Ok(file), Ok(path)) if file.starts_with(&path) => file.strip_prefix(&path)?.into(),
+        _ => Path::new("").join_files_dir().join(file_name),
+    };
+
+    Ok(Mod::new(
+        name,
+        None,
+        None,
+        Source::Path {
+            path: relative
+                .to_str()
+                .wrap_err("invalid file path")?
+                .replace('\\', "/"),
+        },
+        file_kind(file_name, kind)?,
+    ))
+}
+
+/// Returns the kind of content of a file, which defaults to mods for jar files.
+fn file_kind(file_name: &str, kind: Option<ContentKind>) -> eyre::Result<ContentKind> {
+    match kind {
+        Some(kind) => Ok(kind),
+        None if file_name.ends_with(".jar") => Ok(ContentKind::Mod),
+        None => bail!(
+            "can't tell the kind of content of `{}`, use `--kind` to set it",
+            file_name
+        ),
+    }
+}
+
+fn content_kind(project: &modrinth::Project) -> eyre::Result<ContentKind> {
+    ContentKind::from_project_type(&project.project_type)
+        .wrap_err(format!(
+            "`{}` projects can't be added, only `mod`, `resourcepack`, `shader`, `datapack` and `plugin` projects are allowed",
+            project.project_type.as_str()
+        ))
+}
+
+/// Creates a Modrinth source for a project, pinned to a version number or id.
+fn modrinth_mod(project: &modrinth::Project, version: &str, kind: ContentKind) -> Mod {
+    Mod::new(
+        project.slug.clone(),
+        None,
+        None,
+        Source::Modrinth {
+            version: version.to_string(),
+        },
+        kind,
+    )
+    .with_support(project.client_side, project.server_side)
+}
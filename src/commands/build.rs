@@ -1,16 +1,71 @@
 use crate::ops;
-use crate::Project;
+use crate::util::modrinth::ModrinthClient;
+use crate::{Project, Side};
+use eyre::WrapErr;
 use log::info;
 use std::env;
 
 #[derive(clap::Args)]
-pub struct BuildArgs;
+pub struct BuildArgs {
+    /// Output format of the build
+    #[arg(short, long, value_enum, default_value_t = BuildTarget::Instance)]
+    target: BuildTarget,
+
+    /// Require `niterpack.lock` to be up to date
+    #[arg(long)]
+    locked: bool,
+
+    /// Build even if the modpack contains incompatible mods or misses required dependencies
+    #[arg(short, long)]
+    force: bool,
+
+    /// Only include mods needed on this side
+    #[arg(short, long, value_enum)]
+    side: Option<Side>,
+
+    /// Maximum number of concurrent downloads
+    #[arg(short, long, value_parser = clap::value_parser!(u64).range(1..))]
+    jobs: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum BuildTarget {
+    /// A ready to use Minecraft instance in `build/instance`
+    Instance,
+
+    /// A Modrinth modpack archive in `build`
+    Mrpack,
+
+    /// A dedicated server with server-side mods in `build/server`
+    Server,
+}
 
 impl BuildArgs {
     pub fn run(&self) -> eyre::Result<()> {
         let current_dir = env::current_dir().unwrap();
+        let project = Project::read(&current_dir)?;
+        let client = ModrinthClient::from_env().wrap_err("failed to create modrinth client")?;
+        let sources = ops::lock(&client, &project, &current_dir, self.locked)?.sources();
+        ops::check_dependencies(&client, &sources, self.force)?;
+        ops::check_incompatibilities(&sources, self.force)?;
+
+        let sources = match self.side {
+            Some(side) => project.side_sources(sources, side),
+            None => sources,
+        };
+
+        let jobs = self
+            .jobs
+            .map(|jobs| jobs as usize)
+            .or(project.manifest.build.as_ref().and_then(|build| build.jobs))
+            .unwrap_or(ops::DEFAULT_JOBS);
+        let path = current_dir.join("build");
 
-        ops::build(&Project::read(&current_dir)?, current_dir.join("build"))?;
+        match self.target {
+            BuildTarget::Instance => ops::build(&project, &current_dir, sources, path, jobs)?,
+            BuildTarget::Mrpack => ops::build_mrpack(&project, &current_dir, sources, path)?,
+            BuildTarget::Server => ops::build_server(&project, &current_dir, sources, path, jobs)?,
+        }
 
         info!("Finished building modpack");
         Ok(())
@@ -0,0 +1,82 @@
+use crate::cache::Cache;
+use log::info;
+use std::time::Duration;
+
+#[derive(clap::Args)]
+pub struct CacheArgs {
+    #[command(subcommand)]
+    command: CacheCommands,
+}
+
+#[derive(clap::Subcommand)]
+pub enum CacheCommands {
+    /// List all cached files
+    List,
+
+    /// Remove cached files that haven't been used recently
+    Prune {
+        /// Remove files that haven't been used for this many days
+        #[arg(short, long, default_value_t = 30)]
+        days: u64,
+    },
+
+    /// Remove all cached files
+    Clean,
+}
+
+impl CacheArgs {
+    pub fn run(&self) -> eyre::Result<()> {
+        let cache = Cache::open()?;
+
+        match &self.command {
+            CacheCommands::List => {
+                let mut entries = cache.entries()?;
+                entries.sort_by(|a, b| a.file.cmp(&b.file));
+
+                for entry in &entries {
+                    info!("{} ({})", entry.file, format_size(entry.size));
+                }
+
+                info!(
+                    "{} files, {} in {}",
+                    entries.len(),
+                    format_size(entries.iter().map(|entry| entry.size).sum()),
+                    cache.path.display()
+                );
+            }
+            CacheCommands::Prune { days } => {
+                let removed = cache.prune(Duration::from_secs(days * 24 * 60 * 60))?;
+
+                info!(
+                    "Removed {} files, {}",
+                    removed.len(),
+                    format_size(removed.iter().map(|entry| entry.size).sum())
+                );
+            }
+            CacheCommands::Clean => {
+                cache.clean()?;
+
+                info!("Removed all cached files");
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn format_size(size: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+
+    let mut size = size as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{} {}", size, UNITS[unit])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
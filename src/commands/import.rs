@@ -0,0 +1,42 @@
+use crate::ops;
+use crate::toml::JoinToml;
+use crate::util::modrinth::ModrinthClient;
+use eyre::{ensure, WrapErr};
+use log::info;
+use std::env;
+use std::path::PathBuf;
+
+#[derive(clap::Args)]
+pub struct ImportArgs {
+    /// Path to the `.mrpack` file to import
+    #[arg(id = "FILE", required_unless_present = "instance")]
+    file: Option<PathBuf>,
+
+    /// Import an existing instance directory instead
+    ///
+    /// Supports Prism Launcher and MultiMC instances, and `.minecraft` folders.
+    #[arg(long, conflicts_with = "FILE")]
+    instance: Option<PathBuf>,
+}
+
+impl ImportArgs {
+    pub fn run(&self) -> eyre::Result<()> {
+        let current_dir = env::current_dir().unwrap();
+
+        ensure!(
+            !current_dir.join_manifest_file().exists(),
+            "`niterpack.toml` already exists in the current directory"
+        );
+
+        let client = ModrinthClient::from_env().wrap_err("failed to create modrinth client")?;
+        let project = match (&self.instance, &self.file) {
+            (Some(instance), _) => ops::import_instance(&client, instance, &current_dir)?,
+            (None, Some(file)) => ops::import_mrpack(&client, file, &current_dir)?,
+            (None, None) => unreachable!("clap requires a file or an instance"),
+        };
+        project.write(&current_dir)?;
+
+        info!("Imported modpack `{}`", &project.manifest.name);
+        Ok(())
+    }
+}
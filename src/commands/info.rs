@@ -0,0 +1,134 @@
+use crate::source;
+use crate::toml::JoinToml;
+use crate::util::modrinth::{self, error::NotFound, ModrinthClient};
+use crate::{ContentKind, Project, Side, Source};
+use eyre::{ContextCompat, WrapErr};
+use log::info;
+use std::env;
+
+/// Number of compatible versions shown.
+const VERSIONS: usize = 10;
+
+#[derive(clap::Args)]
+pub struct InfoArgs {
+    /// Reference to a project, or the name of a mod in the modpack
+    ///
+    /// You can reference a project using a slug, or an id.
+    #[arg(id = "MOD")]
+    mod_name: String,
+}
+
+impl InfoArgs {
+    pub fn run(&self) -> eyre::Result<()> {
+        let current_dir = env::current_dir().unwrap();
+        let client = ModrinthClient::from_env().wrap_err("failed to create modrinth client")?;
+
+        let modpack = if current_dir.join_manifest_file().exists() {
+            Some(Project::read(&current_dir)?)
+        } else {
+            None
+        };
+        let manifest = modpack.as_ref().map(|modpack| &modpack.manifest);
+
+        let project = client
+            .project(&self.mod_name)
+            .not_found()
+            .wrap_err("failed to fetch modrinth project")?
+            .wrap_err(format!("project `{}` not found", &self.mod_name))?;
+        let kind = ContentKind::from_project_type(&project.project_type).unwrap_or_default();
+
+        info!("{} ({})", project.title, project.slug);
+        info!("{}", project.description);
+        info!("");
+        info!("License: {} ({})", project.license.name, project.license.id);
+        info!(
+            "Side: {} (client {}, server {})",
+            Side::from_support(project.client_side, project.server_side).as_str(),
+            project.client_side.as_str(),
+            project.server_side.as_str()
+        );
+        if let Some(source_url) = &project.source_url {
+            info!("Source: {}", source_url);
+        }
+        if let Some(issues_url) = &project.issues_url {
+            info!("Issues: {}", issues_url);
+        }
+
+        let versions = client
+            .project_versions(
+                &project.id,
+                manifest
+                    .and_then(|manifest| kind.loaders(manifest))
+                    .as_deref(),
+                manifest.and_then(|manifest| manifest.minecraft_version.as_deref()),
+            )
+            .wrap_err("failed to fetch project versions")?;
+
+        info!("");
+        if versions.is_empty() {
+            info!("No compatible versions");
+        } else {
+            info!("Compatible versions:");
+            for version in versions.iter().take(VERSIONS) {
+                info!("  {}", describe(version));
+            }
+            if versions.len() > VERSIONS {
+                info!("  and {} more", versions.len() - VERSIONS);
+            }
+        }
+
+        // Compare the pinned version of mods already in the modpack
+        let Some(modpack) = &modpack else {
+            return Ok(());
+        };
+        let Some((mod_data, version)) = modpack.mods.iter().find_map(|mod_data| {
+            if mod_data.kind != kind
+                || (mod_data.name != self.mod_name
+                    && mod_data.name != project.slug
+                    && mod_data.name != project.id)
+            {
+                return None;
+            }
+            match &mod_data.source {
+                Source::Modrinth { version } => Some((mod_data, version)),
+                _ => None,
+            }
+        }) else {
+            return Ok(());
+        };
+
+        let manifest = &modpack.manifest;
+        let pinned =
+            source::modrinth_version(&client, manifest, kind, &project.id, version).wrap_err(
+                format!("failed to fetch pinned version of `{}`", mod_data.name),
+            )?;
+
+        info!("");
+        info!("Pinned version: {}", describe(&pinned));
+        if let Some(loader) = &manifest.loader {
+            if pinned.loaders.contains(loader) {
+                info!("  supports loader `{}`", loader);
+            } else {
+                info!("  does not support loader `{}`", loader);
+            }
+        }
+        if let Some(minecraft_version) = &manifest.minecraft_version {
+            if pinned.game_versions.contains(minecraft_version) {
+                info!("  supports Minecraft {}", minecraft_version);
+            } else {
+                info!("  does not support Minecraft {}", minecraft_version);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn describe(version: &modrinth::Version) -> String {
+    format!(
+        "{} ({}; {})",
+        version.version_number,
+        version.loaders.join(", "),
+        version.game_versions.join(", ")
+    )
+}
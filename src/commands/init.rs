@@ -18,6 +18,7 @@ impl InitArgs {
             String::from("0.1.0"),
             None,
             None,
+            None,
         ));
 
         project.write(current_dir)?;
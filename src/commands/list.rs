@@ -0,0 +1,221 @@
+use super::table::print_table;
+use crate::source::SourceKind;
+use crate::toml::JoinToml;
+use crate::{Lockfile, Project, Side, Source};
+use eyre::WrapErr;
+use log::info;
+use serde::Serialize;
+use std::env;
+use std::io::{self, Write};
+use std::path::Path;
+
+#[derive(clap::Args)]
+pub struct ListArgs {
+    /// Output format of the list
+    #[arg(short, long, value_enum, default_value_t = ListFormat::Table)]
+    format: ListFormat,
+
+    /// Only list mods with this kind of source
+    #[arg(short, long, value_enum)]
+    source: Option<SourceKind>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ListFormat {
+    /// An aligned table
+    Table,
+
+    /// A JSON array of mods
+    Json,
+
+    /// Comma-separated values with a header row
+    Csv,
+}
+
+#[derive(Debug, Serialize)]
+struct Entry<'a> {
+    name: &'a str,
+    source: SourceKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    repo: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    url: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    path: Option<&'a str>,
+    side: Option<Side>,
+    file: Option<&'a str>,
+}
+
+impl Entry<'_> {
+    fn row(&self) -> [String; 6] {
+        [
+            self.name.to_string(),
+            self.source.as_str().to_string(),
+            self.repo.unwrap_or_default().to_string(),
+            self.version
+                .or(self.url)
+                .or(self.path)
+                .unwrap_or_default()
+                .to_string(),
+            self.side
+                .map(|side| side.as_str())
+                .unwrap_or_default()
+                .to_string(),
+            self.file.unwrap_or_default().to_string(),
+        ]
+    }
+}
+
+impl ListArgs {
+    pub fn run(&self) -> eyre::Result<()> {
+        let current_dir = env::current_dir().unwrap();
+        let project = Project::read(&current_dir)?;
+
+        // Listing works offline, file names are only known for local files and mods in
+        // the lock file
+        let lock_path = current_dir.join_lock_file();
+        let lockfile = if lock_path.exists() {
+            Some(Lockfile::read(&lock_path).wrap_err("failed to read lock file")?)
+        } else {
+            None
+        };
+
+        let mut mods: Vec<_> = project
+            .mods
+            .iter()
+            .filter(|mod_data| {
+                self.source
+                    .is_none_or(|source| mod_data.source.kind() == source)
+            })
+            .collect();
+        mods.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let entries: Vec<Entry> = mods
+            .into_iter()
+            .map(|mod_data| {
+                let (repo, version, url, path) = match &mod_data.source {
+                    Source::Download { url, .. } => (None, None, Some(url.as_str()), None),
+                    Source::Modrinth { version } => (None, Some(version.as_str()), None, None),
+                    Source::Path { path } => (None, None, None, Some(path.as_str())),
+                    Source::GitHub { repo, tag, .. } => {
+                        (Some(repo.as_str()), Some(tag.as_str()), None, None)
+                    }
+                };
+
+                Entry {
+                    name: &mod_data.name,
+                    source: mod_data.source.kind(),
+                    repo,
+                    version,
+                    url,
+                    path,
+                    side: mod_data.side,
+                    file: match &mod_data.source {
+                        Source::Path { path } => {
+                            Path::new(path).file_name().and_then(|name| name.to_str())
+                        }
+                        _ => lockfile
+                            .iter()
+                            .flat_map(|lockfile| &lockfile.mods)
+                            .find(|locked| {
+                                locked.build.name == mod_data.name
+                                    && locked.build.kind == mod_data.kind
+                                    && locked.source == mod_data.source
+                            })
+                            .map(|locked| locked.build.file.as_str()),
+                    },
+                }
+            })
+            .collect();
+
+        match self.format {
+            ListFormat::Table => print_entries(&entries),
+            ListFormat::Json => write_stdout(&serde_json::to_string_pretty(&entries)?)?,
+            ListFormat::Csv => {
+                let mut output = String::from("name,source,repo,version,side,file");
+                for entry in &entries {
+                    output.push('\n');
+                    output.push_str(
+                        &entry
+                            .row()
+                            .iter()
+                            .map(|cell| csv_field(cell))
+                            .collect::<Vec<_>>()
+                            .join(","),
+                    );
+                }
+                write_stdout(&output)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn print_entries(entries: &[Entry]) {
+    if entries.is_empty() {
+        info!("No mods found");
+        return;
+    }
+
+    let mut rows = vec![[
+        String::from("Name"),
+        String::from("Source"),
+        String::from("Repo"),
+        String::from("Version"),
+        String::from("Side"),
+        String::from("File"),
+    ]];
+    rows.extend(entries.iter().map(|entry| {
+        entry.row().map(|cell| {
+            if cell.is_empty() {
+                String::from("-")
+            } else {
+                cell
+            }
+        })
+    }));
+
+    print_table(&rows, &[]);
+}
+
+/// Writes machine-readable output directly, without going through the logger.
+///
+/// A closed pipe isn't an error, the reader only wanted part of the output.
+fn write_stdout(output: &str) -> io::Result<()> {
+    let mut stdout = io::stdout().lock();
+    match writeln!(stdout, "{}", output).and_then(|_| stdout.flush()) {
+        Err(err) if err.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        result => result,
+    }
+}
+
+/// Quotes a CSV field if it contains special characters.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_plain_fields() {
+        assert_eq!(csv_field(""), "");
+        assert_eq!(csv_field("sodium-0.4.10.jar"), "sodium-0.4.10.jar");
+    }
+
+    #[test]
+    fn quotes_special_fields() {
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("a\nb"), "\"a\nb\"");
+        assert_eq!(csv_field("a\rb"), "\"a\rb\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+}
@@ -1,30 +1,73 @@
 mod add;
 mod build;
+mod cache;
+mod import;
+mod info;
 mod init;
+mod list;
+mod outdated;
 mod remove;
+mod search;
+mod table;
+mod update;
 
 #[derive(clap::Subcommand)]
 pub enum Commands {
     /// Create a new modpack in the current directory
     Init(init::InitArgs),
 
+    /// Create a new modpack in the current directory from a `.mrpack` file or an instance
+    Import(import::ImportArgs),
+
     /// Add a new mod to the current modpack
     Add(add::AddArgs),
 
     /// Remove a mod from the current modpack
     Remove(remove::RemoveArgs),
 
+    /// Search for projects on Modrinth
+    ///
+    /// Inside a modpack, only projects compatible with its loader and Minecraft version
+    /// are shown.
+    Search(search::SearchArgs),
+
+    /// List the mods of the current modpack
+    List(list::ListArgs),
+
+    /// Update mods to their newest compatible versions
+    Update(update::UpdateArgs),
+
+    /// Show details about a Modrinth project
+    ///
+    /// For mods in the modpack, the pinned version is compared with the manifest.
+    Info(info::InfoArgs),
+
+    /// List mods that have newer versions available
+    ///
+    /// Exits with an error if any mod has a compatible update.
+    Outdated(outdated::OutdatedArgs),
+
     /// Build the current modpack
     Build(build::BuildArgs),
+
+    /// Manage the shared download cache
+    Cache(cache::CacheArgs),
 }
 
 impl Commands {
     pub fn run(&self) -> eyre::Result<()> {
         match &self {
             Commands::Init(args) => args.run(),
+            Commands::Import(args) => args.run(),
             Commands::Add(args) => args.run(),
             Commands::Remove(args) => args.run(),
+            Commands::Search(args) => args.run(),
+            Commands::List(args) => args.run(),
+            Commands::Update(args) => args.run(),
+            Commands::Info(args) => args.run(),
+            Commands::Outdated(args) => args.run(),
             Commands::Build(args) => args.run(),
+            Commands::Cache(args) => args.run(),
         }
     }
 }
@@ -0,0 +1,69 @@
+use super::table::print_table;
+use crate::util::modrinth::ModrinthClient;
+use crate::{ops, Project};
+use eyre::{bail, WrapErr};
+use log::info;
+use std::env;
+
+#[derive(clap::Args)]
+pub struct OutdatedArgs;
+
+impl OutdatedArgs {
+    pub fn run(&self) -> eyre::Result<()> {
+        let current_dir = env::current_dir().unwrap();
+        let client = ModrinthClient::from_env().wrap_err("failed to create modrinth client")?;
+        let project = Project::read(&current_dir)?;
+
+        let mut mods = project.mods.clone();
+        mods.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let mut rows = vec![[
+            String::from("Mod"),
+            String::from("Current"),
+            String::from("Compatible"),
+            String::from("Latest"),
+        ]];
+        let mut outdated = 0;
+        for mod_data in &mods {
+            let Some(entry) = ops::outdated(&client, &project.manifest, mod_data).wrap_err(
+                format!("failed to check updates for mod `{}`", mod_data.name),
+            )?
+            else {
+                continue;
+            };
+
+            if entry.update.is_some() {
+                outdated += 1;
+            }
+
+            let mut latest = entry
+                .latest
+                .as_ref()
+                .map(|version| version.version_number.clone())
+                .unwrap_or_else(|| String::from("-"));
+            if entry.other_minecraft_only(&project.manifest) {
+                latest.push_str(" (other Minecraft versions only)");
+            }
+
+            rows.push([
+                entry.name.clone(),
+                entry.current.clone(),
+                entry
+                    .compatible
+                    .as_ref()
+                    .map(|version| version.version_number.clone())
+                    .unwrap_or_else(|| String::from("-")),
+                latest,
+            ]);
+        }
+
+        print_table(&rows, &[]);
+
+        if outdated > 0 {
+            bail!("{} mods have compatible updates", outdated);
+        }
+
+        info!("All mods are up to date");
+        Ok(())
+    }
+}
@@ -1,5 +1,6 @@
 use crate::toml::JoinToml;
-use eyre::ensure;
+use crate::ContentKind;
+use eyre::{ensure, ContextCompat};
 use log::info;
 use std::{env, fs};
 
@@ -19,13 +20,18 @@ impl RemoveArgs {
             "could not find `niterpack.toml` in the current directory"
         );
 
-        let mod_path = current_dir.join_mods_dir().join_mod_file(&self.mod_name);
-
-        ensure!(
-            mod_path.exists(),
-            "mod `{}` doesn't exist in this modpack",
-            &self.mod_name
-        );
+        let mod_path = ContentKind::ALL
+            .iter()
+            .map(|kind| {
+                current_dir
+                    .join_content_dir(*kind)
+                    .join_mod_file(&self.mod_name)
+            })
+            .find(|mod_path| mod_path.exists())
+            .wrap_err(format!(
+                "mod `{}` doesn't exist in this modpack",
+                &self.mod_name
+            ))?;
 
         fs::remove_file(mod_path)?;
 
@@ -0,0 +1,107 @@
+use super::table::print_table;
+use crate::ops;
+use crate::toml::JoinToml;
+use crate::util::modrinth::{ModrinthClient, ProjectType};
+use eyre::WrapErr;
+use log::info;
+use std::env;
+
+/// Maximum length of the descriptions shown in the results.
+const DESCRIPTION_WIDTH: usize = 60;
+
+#[derive(clap::Args)]
+pub struct SearchArgs {
+    /// Text to search for
+    #[arg(id = "QUERY")]
+    query: String,
+
+    /// Type of the projects to search
+    #[arg(short = 't', long = "type", value_enum, default_value_t = SearchType::Mod)]
+    project_type: SearchType,
+
+    /// Maximum number of results
+    #[arg(short, long, default_value_t = 10)]
+    limit: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum SearchType {
+    Mod,
+    Resourcepack,
+    Shader,
+    Datapack,
+    Plugin,
+}
+
+impl From<SearchType> for ProjectType {
+    fn from(value: SearchType) -> Self {
+        match value {
+            SearchType::Mod => ProjectType::Mod,
+            SearchType::Resourcepack => ProjectType::ResourcePack,
+            SearchType::Shader => ProjectType::Shader,
+            SearchType::Datapack => ProjectType::Datapack,
+            SearchType::Plugin => ProjectType::Plugin,
+        }
+    }
+}
+
+impl SearchArgs {
+    pub fn run(&self) -> eyre::Result<()> {
+        let current_dir = env::current_dir().unwrap();
+        let client = ModrinthClient::from_env().wrap_err("failed to create modrinth client")?;
+
+        // Filter by the modpack's loader and version when inside one
+        let manifest_path = current_dir.join_manifest_file();
+        let manifest = if manifest_path.exists() {
+            Some(
+                crate::toml::read_manifest(&manifest_path)
+                    .wrap_err("failed to read manifest file")?,
+            )
+        } else {
+            None
+        };
+
+        let hits = ops::search(
+            &client,
+            manifest.as_ref(),
+            &self.query,
+            &self.project_type.into(),
+            self.limit,
+        )?;
+
+        if hits.is_empty() {
+            info!("No projects found");
+            return Ok(());
+        }
+
+        let mut rows = vec![[
+            String::from("Slug"),
+            String::from("Title"),
+            String::from("Downloads"),
+            String::from("Description"),
+        ]];
+        for hit in hits {
+            rows.push([
+                hit.slug,
+                hit.title,
+                hit.downloads.to_string(),
+                truncate(&hit.description, DESCRIPTION_WIDTH),
+            ]);
+        }
+
+        print_table(&rows, &[2]);
+
+        Ok(())
+    }
+}
+
+fn truncate(text: &str, width: usize) -> String {
+    let line = text.lines().next().unwrap_or_default();
+    if line.chars().count() <= width {
+        return line.to_string();
+    }
+
+    let mut truncated: String = line.chars().take(width - 3).collect();
+    truncated.push_str("...");
+    truncated
+}
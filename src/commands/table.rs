@@ -0,0 +1,69 @@
+use log::info;
+
+/// Prints rows as a table, with the first row as the header.
+///
+/// Columns are aligned to the left, except the ones listed in `right`.
+pub fn print_table<const N: usize>(rows: &[[String; N]], right: &[usize]) {
+    for line in format_table(rows, right) {
+        info!("{}", line);
+    }
+}
+
+/// Formats rows as aligned lines, measuring the cells in characters so that non-ASCII
+/// text lines up.
+fn format_table<const N: usize>(rows: &[[String; N]], right: &[usize]) -> Vec<String> {
+    let mut widths = [0; N];
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    rows.iter()
+        .map(|row| {
+            let cells: Vec<String> = row
+                .iter()
+                .enumerate()
+                .map(|(column, cell)| {
+                    let width = widths[column];
+                    if right.contains(&column) {
+                        format!("{:>width$}", cell)
+                    } else if column == N - 1 {
+                        // Nothing follows the last column, so it isn't padded
+                        cell.to_string()
+                    } else {
+                        format!("{:<width$}", cell)
+                    }
+                })
+                .collect();
+            cells.join("  ")
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row<const N: usize>(cells: [&str; N]) -> [String; N] {
+        cells.map(str::to_string)
+    }
+
+    #[test]
+    fn aligns_columns_by_characters() {
+        let rows = [
+            row(["Name", "Downloads", "Description"]),
+            row(["söдium", "5", "Fast"]),
+            row(["lithium", "12", "Faster"]),
+        ];
+
+        assert_eq!(
+            format_table(&rows, &[1]),
+            [
+                "Name     Downloads  Description",
+                "söдium           5  Fast",
+                "lithium         12  Faster",
+            ]
+        );
+    }
+}
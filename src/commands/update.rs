@@ -0,0 +1,88 @@
+use crate::toml::JoinToml;
+use crate::util::modrinth::ModrinthClient;
+use crate::{ops, Project, Source};
+use eyre::{ensure, WrapErr};
+use log::info;
+use std::env;
+
+#[derive(clap::Args)]
+pub struct UpdateArgs {
+    /// Names of the mods to update
+    ///
+    /// All mods are updated if no names are specified.
+    #[arg(id = "MOD")]
+    mod_names: Vec<String>,
+
+    /// Only show the available updates without changing any files
+    #[arg(long)]
+    dry_run: bool,
+}
+
+impl UpdateArgs {
+    pub fn run(&self) -> eyre::Result<()> {
+        let current_dir = env::current_dir().unwrap();
+        let client = ModrinthClient::from_env().wrap_err("failed to create modrinth client")?;
+        let project = Project::read(&current_dir)?;
+
+        for mod_name in &self.mod_names {
+            ensure!(
+                project
+                    .mods
+                    .iter()
+                    .any(|mod_data| &mod_data.name == mod_name),
+                "mod `{}` doesn't exist in this modpack",
+                mod_name
+            );
+        }
+
+        let mut updated = 0;
+        for mod_data in &project.mods {
+            if !self.mod_names.is_empty() && !self.mod_names.contains(&mod_data.name) {
+                continue;
+            }
+
+            let Source::Modrinth { version } = &mod_data.source else {
+                continue;
+            };
+
+            let Some(update) = ops::find_update(&client, &project.manifest, mod_data).wrap_err(
+                format!("failed to check updates for mod `{}`", mod_data.name),
+            )?
+            else {
+                continue;
+            };
+
+            info!(
+                "{} {} -> {}",
+                mod_data.name, version, update.version.version_number
+            );
+            updated += 1;
+
+            if self.dry_run {
+                continue;
+            }
+
+            let mut mod_data = mod_data.clone();
+            mod_data.source = Source::Modrinth {
+                version: update.pin(),
+            };
+
+            crate::toml::write_mod(
+                current_dir
+                    .join_content_dir(mod_data.kind)
+                    .join_mod_file(&mod_data.name),
+                mod_data,
+            )
+            .wrap_err("failed to write mod file")?;
+        }
+
+        if updated == 0 {
+            info!("All mods are up to date");
+        } else if self.dry_run {
+            info!("{} mods can be updated", updated);
+        } else {
+            info!("Updated {} mods", updated);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,207 @@
+use crate::source::BuildSource;
+use crate::util::modrinth::ModrinthClient;
+use crate::{Mod, Project, Source};
+use eyre::Result;
+use std::path::Path;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lockfile {
+    pub minecraft_version: Option<String>,
+    pub loader: Option<String>,
+    pub mods: Vec<LockedMod>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LockedMod {
+    pub source: Source,
+    pub build: BuildSource,
+}
+
+impl Lockfile {
+    pub fn new(
+        minecraft_version: Option<String>,
+        loader: Option<String>,
+        mods: Vec<LockedMod>,
+    ) -> Self {
+        Self {
+            minecraft_version,
+            loader,
+            mods,
+        }
+    }
+
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<Self> {
+        crate::toml::read_lockfile(path)
+    }
+
+    pub fn write<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        crate::toml::write_lockfile(path, self.clone())
+    }
+
+    /// Resolves all mods of a project, reusing entries from a previous lockfile
+    /// for mods that haven't changed since.
+    pub fn resolve(
+        client: &ModrinthClient,
+        project: &Project,
+        previous: Option<&Lockfile>,
+    ) -> Result<Self> {
+        let manifest = &project.manifest;
+        let previous = previous.filter(|lockfile| {
+            lockfile.minecraft_version == manifest.minecraft_version
+                && lockfile.loader == manifest.loader
+        });
+
+        let locked: Vec<Option<&LockedMod>> = project
+            .mods
+            .iter()
+            .map(|mod_data| {
+                previous.and_then(|lockfile| {
+                    lockfile.mods.iter().find(|locked| {
+                        locked.build.name == mod_data.name
+                            && locked.build.kind == mod_data.kind
+                            && locked.source == mod_data.source
+                    })
+                })
+            })
+            .collect();
+
+        // Resolve every mod missing from the previous lockfile at once
+        let unlocked: Vec<&Mod> = project
+            .mods
+            .iter()
+            .zip(&locked)
+            .filter(|(_, locked)| locked.is_none())
+            .map(|(mod_data, _)| mod_data)
+            .collect();
+        let mut resolved = BuildSource::generate_all(client, manifest, &unlocked)?.into_iter();
+
+        let mut mods = Vec::new();
+        for (mod_data, locked) in project.mods.iter().zip(locked) {
+            mods.push(match locked {
+                Some(locked) => locked.clone(),
+                None => LockedMod {
+                    source: mod_data.source.clone(),
+                    build: resolved
+                        .next()
+                        .expect("every unlocked mod should have been resolved"),
+                },
+            });
+        }
+        mods.sort_by(|a, b| a.build.name.cmp(&b.build.name));
+
+        Ok(Lockfile::new(
+            manifest.minecraft_version.clone(),
+            manifest.loader.clone(),
+            mods,
+        ))
+    }
+
+    pub fn sources(&self) -> Vec<BuildSource> {
+        self.mods
+            .iter()
+            .map(|locked| locked.build.clone())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ContentKind, Manifest};
+
+    fn project(minecraft_version: &str, mods: Vec<Mod>) -> Project {
+        Project::new(
+            Manifest::new(
+                "pack".to_string(),
+                "1.0.0".to_string(),
+                Some(minecraft_version.to_string()),
+                Some("fabric".to_string()),
+                None,
+            ),
+            mods,
+            None,
+            None,
+        )
+    }
+
+    fn download(name: &str, url: &str) -> Mod {
+        Mod::new(
+            name.to_string(),
+            None,
+            None,
+            Source::Download {
+                url: url.to_string(),
+                sha512: None,
+                sha1: None,
+            },
+            ContentKind::Mod,
+        )
+    }
+
+    // Download sources resolve without any request, so no server is needed
+    fn client() -> ModrinthClient {
+        ModrinthClient::new("http://127.0.0.1:9".to_string(), "test", None).unwrap()
+    }
+
+    /// Marks the entries of a lockfile, so that reused entries can be told apart.
+    fn marked(mut lockfile: Lockfile) -> Lockfile {
+        for locked in &mut lockfile.mods {
+            locked.build.size = Some(1);
+        }
+        lockfile
+    }
+
+    #[test]
+    fn reuses_fresh_entries() {
+        let project = project("1.20.1", vec![download("a", "https://example.com/a.jar")]);
+        let previous = marked(Lockfile::resolve(&client(), &project, None).unwrap());
+
+        let lockfile = Lockfile::resolve(&client(), &project, Some(&previous)).unwrap();
+
+        assert_eq!(lockfile, previous);
+    }
+
+    #[test]
+    fn resolves_changed_sources() {
+        let previous = marked(
+            Lockfile::resolve(
+                &client(),
+                &project(
+                    "1.20.1",
+                    vec![
+                        download("a", "https://example.com/a.jar"),
+                        download("b", "https://example.com/b.jar"),
+                    ],
+                ),
+                None,
+            )
+            .unwrap(),
+        );
+
+        let project = project(
+            "1.20.1",
+            vec![
+                download("a", "https://example.com/a.jar"),
+                download("b", "https://example.com/b-2.jar"),
+            ],
+        );
+        let lockfile = Lockfile::resolve(&client(), &project, Some(&previous)).unwrap();
+
+        assert_eq!(lockfile.mods[0], previous.mods[0]);
+        assert_eq!(lockfile.mods[1].build.file, "b-2.jar");
+        assert_eq!(lockfile.mods[1].build.size, None);
+    }
+
+    #[test]
+    fn resolves_everything_for_other_minecraft_versions() {
+        let mods = vec![download("a", "https://example.com/a.jar")];
+        let previous =
+            marked(Lockfile::resolve(&client(), &project("1.20.1", mods.clone()), None).unwrap());
+
+        let lockfile =
+            Lockfile::resolve(&client(), &project("1.20.2", mods), Some(&previous)).unwrap();
+
+        assert_eq!(lockfile.minecraft_version.as_deref(), Some("1.20.2"));
+        assert_eq!(lockfile.mods[0].build.size, None);
+    }
+}
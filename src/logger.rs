@@ -1,20 +1,29 @@
 use log::{Level, LevelFilter, Metadata, Record};
 use termcolor::{BufferWriter, Color, ColorChoice, ColorSpec, WriteColor};
 
-pub fn init() {
-    log::set_boxed_logger(Box::new(NiterLogger::new()))
-        .map(|()| log::set_max_level(LevelFilter::Info))
+/// Installs the logger, showing debug messages when `verbose` is set.
+pub fn init(verbose: bool) {
+    let level = if verbose {
+        LevelFilter::Debug
+    } else {
+        LevelFilter::Info
+    };
+
+    log::set_boxed_logger(Box::new(NiterLogger::new(level)))
+        .map(|()| log::set_max_level(level))
         .expect("could not set logger")
 }
 
 pub struct NiterLogger {
+    level: LevelFilter,
     writer: BufferWriter,
     err_writer: BufferWriter,
 }
 
 impl NiterLogger {
-    pub fn new() -> NiterLogger {
+    pub fn new(level: LevelFilter) -> NiterLogger {
         NiterLogger {
+            level,
             writer: BufferWriter::stdout(ColorChoice::Auto),
             err_writer: BufferWriter::stderr(ColorChoice::Auto),
         }
@@ -23,7 +32,10 @@ impl NiterLogger {
 
 impl log::Log for NiterLogger {
     fn enabled(&self, metadata: &Metadata) -> bool {
+        // Debug messages of dependencies, like reqwest, are too noisy to show
         metadata.level() <= Level::Info
+            || (metadata.level() <= self.level
+                && metadata.target().starts_with(env!("CARGO_CRATE_NAME")))
     }
 
     fn log(&self, record: &Record) {
@@ -1,6 +1,8 @@
 extern crate core;
 
+mod cache;
 mod commands;
+mod lock;
 mod logger;
 mod manifest;
 mod ops;
@@ -9,24 +11,29 @@ mod source;
 mod toml;
 mod util;
 
+pub use lock::*;
 pub use manifest::*;
 pub use project::*;
 pub use source::Source;
 
 use crate::commands::Commands;
-use clap::{command, Parser};
+use clap::Parser;
 
 #[derive(clap::Parser)]
 #[command(author, version, about, long_about = None)]
 struct Cli {
+    /// Show more details, like the outcome of every mod when building
+    #[arg(long, global = true)]
+    verbose: bool,
+
     #[command(subcommand)]
     command: Commands,
 }
 
 fn main() -> eyre::Result<()> {
-    logger::init();
-
     let cli = Cli::parse();
+    logger::init(cli.verbose);
+
     cli.command.run()?;
 
     Ok(())
@@ -1,4 +1,7 @@
-use crate::Project;
+use crate::{ContentKind, Project};
+use eyre::{ensure, Result};
+use std::collections::BTreeMap;
+use std::path::{Component, Path};
 
 #[derive(Debug, Clone)]
 pub struct Manifest {
@@ -6,6 +9,24 @@ pub struct Manifest {
     pub version: String,
     pub minecraft_version: Option<String>,
     pub loader: Option<String>,
+    pub loader_version: Option<String>,
+    pub server: Option<ServerManifest>,
+    pub build: Option<BuildManifest>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct BuildManifest {
+    pub jobs: Option<usize>,
+    pub datapacks_dir: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ServerManifest {
+    pub eula: bool,
+    pub start_scripts: bool,
+    pub jar: Option<String>,
+    pub memory: Option<String>,
+    pub properties: BTreeMap<String, String>,
 }
 
 impl Manifest {
@@ -14,14 +35,71 @@ impl Manifest {
         version: String,
         minecraft_version: Option<String>,
         loader: Option<String>,
+        loader_version: Option<String>,
     ) -> Self {
         Self {
             name,
             version,
             minecraft_version,
             loader,
+            loader_version,
+            server: None,
+            build: None,
         }
     }
+
+    /// Returns the loaders of the plugins the loader can run, if it is a plugin server.
+    ///
+    /// Servers run the plugins of the servers they are forked from, except Folia which
+    /// needs plugins written for it.
+    pub fn plugin_loaders(&self) -> &'static [&'static str] {
+        match self.loader.as_deref() {
+            Some("purpur") => &["purpur", "paper", "spigot", "bukkit"],
+            Some("paper") => &["paper", "spigot", "bukkit"],
+            Some("spigot") => &["spigot", "bukkit"],
+            Some("bukkit") => &["bukkit"],
+            Some("folia") => &["folia"],
+            _ => &[],
+        }
+    }
+
+    /// Whether the loader is a server that can run plugins.
+    pub fn supports_plugins(&self) -> bool {
+        !self.plugin_loaders().is_empty()
+    }
+}
+
+impl BuildManifest {
+    /// Checks that the datapacks directory stays inside the instance, apart from the
+    /// directories of the other kinds of content.
+    pub fn check(&self) -> Result<()> {
+        let Some(dir) = &self.datapacks_dir else {
+            return Ok(());
+        };
+
+        let path = Path::new(dir);
+        ensure!(
+            path.components().next().is_some()
+                && path
+                    .components()
+                    .all(|component| matches!(component, Component::Normal(_))),
+            "datapacks directory `{}` must be a relative path inside the instance",
+            dir
+        );
+
+        for kind in ContentKind::ALL {
+            let other = Path::new(kind.dir_name());
+            ensure!(
+                kind == ContentKind::Datapack
+                    || !(path.starts_with(other) || other.starts_with(path)),
+                "datapacks directory `{}` overlaps the `{}` directory",
+                dir,
+                kind.dir_name()
+            );
+        }
+
+        Ok(())
+    }
 }
 
 impl From<Project> for Manifest {
@@ -29,3 +107,42 @@ impl From<Project> for Manifest {
         value.manifest
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build(datapacks_dir: &str) -> BuildManifest {
+        BuildManifest {
+            jobs: None,
+            datapacks_dir: Some(datapacks_dir.to_string()),
+        }
+    }
+
+    #[test]
+    fn accepts_directories_inside_the_instance() {
+        BuildManifest::default().check().unwrap();
+        build("datapacks").check().unwrap();
+        build("saves/world/datapacks").check().unwrap();
+    }
+
+    #[test]
+    fn rejects_directories_outside_the_instance() {
+        for dir in [
+            "",
+            "/datapacks",
+            "../datapacks",
+            "./datapacks",
+            "saves/../datapacks",
+        ] {
+            assert!(build(dir).check().is_err(), "{}", dir);
+        }
+    }
+
+    #[test]
+    fn rejects_directories_of_other_content() {
+        for dir in ["mods", "mods/datapacks", "resourcepacks", "plugins"] {
+            assert!(build(dir).check().is_err(), "{}", dir);
+        }
+    }
+}
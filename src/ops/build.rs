@@ -1,22 +1,62 @@
+use crate::cache::{self, Cache};
 use crate::source::BuildSource;
-use crate::Project;
-use eyre::{Result, WrapErr};
-use log::info;
-use sha2::{Digest, Sha512};
-use std::ffi::OsString;
-use std::fs::{self, File};
+use crate::util::download::{self, DownloadReport};
+use crate::util::{hash, parallel};
+use crate::{ContentKind, Manifest, Project};
+use eyre::{bail, ensure, ContextCompat, Result, WrapErr};
+use log::{debug, error, info};
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsStr;
+use std::fs;
 use std::io;
 use std::path::{Path, PathBuf};
 
-pub fn build(project: &Project, path: PathBuf) -> Result<()> {
-    let sources = project.build_sources()?;
-    build_instance(project, sources, path.join("instance"))
+/// Number of concurrent downloads used when no limit is configured.
+pub const DEFAULT_JOBS: usize = 8;
+
+/// Directory datapacks are installed into when none is configured.
+pub const DEFAULT_DATAPACKS_DIR: &str = "datapacks";
+
+/// File of an instance listing the files installed by the last build, relative to the
+/// instance.
+const INSTALLED_FILE: &str = ".niterpack-installed";
+
+pub fn build(
+    project: &Project,
+    root: &Path,
+    sources: Vec<BuildSource>,
+    path: PathBuf,
+    jobs: usize,
+) -> Result<()> {
+    // Plugins are only used by servers
+    let sources = sources
+        .into_iter()
+        .filter(|source| source.kind != ContentKind::Plugin)
+        .collect();
+
+    build_instance(project, root, sources, path.join("instance"), jobs)
 }
 
+/// Returns the directory of an instance holding a kind of content, relative to the
+/// instance.
+pub fn instance_dir(manifest: &Manifest, kind: ContentKind) -> String {
+    match kind {
+        ContentKind::Datapack => manifest
+            .build
+            .as_ref()
+            .and_then(|build| build.datapacks_dir.clone())
+            .unwrap_or_else(|| DEFAULT_DATAPACKS_DIR.to_string()),
+        kind => kind.dir_name().to_string(),
+    }
+}
+
+/// Builds an instance, with the local files of the mods read from the project `root`.
 pub fn build_instance(
     project: &Project,
+    root: &Path,
     mut sources: Vec<BuildSource>,
     path: PathBuf,
+    jobs: usize,
 ) -> Result<()> {
     if !path.exists() {
         fs::create_dir_all(&path).wrap_err("failed to create instance directory")?;
@@ -29,93 +69,286 @@ pub fn build_instance(
         copy_recursive(project_config, config_dir).wrap_err("failed to copy config files")?;
     }
 
-    // Download all the mods
-    let mods_dir = path.join("mods");
+    let client = download::client().wrap_err("failed to create a reqwest client")?;
+    let cache = Cache::open()?;
 
-    if !mods_dir.exists() {
-        fs::create_dir(&mods_dir).wrap_err("failed to create mods directory inside instance")?;
-    }
+    check_targets(&project.manifest, &sources)?;
 
-    let client = reqwest::blocking::Client::builder()
-        .build()
-        .wrap_err("failed to create a reqwest client")?;
+    // Hash the local mods, so that they can be compared like any other mod
+    for source in &mut sources {
+        if let Some(local) = &source.path {
+            source.sha512 = Some(
+                hash::sha512(&root.join(local))
+                    .wrap_err(format!("failed to generate sha512 for mod `{}`", local))?,
+            );
+        }
+    }
 
-    for mod_entry in fs::read_dir(&mods_dir)? {
-        let mod_entry = mod_entry?;
+    // Only files installed by a previous build are removed, the others were added by
+    // the game or the user. Instances built before the list existed have their files
+    // treated as installed.
+    let installed_path = path.join(INSTALLED_FILE);
+    let previous: Option<HashSet<PathBuf>> = match fs::read_to_string(&installed_path) {
+        Ok(installed) => Some(installed.lines().map(|line| path.join(line)).collect()),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => None,
+        Err(err) => return Err(err).wrap_err("failed to read installed files"),
+    };
 
-        if let Some(index) = sources
+    // Find the files that are up to date in each content directory
+    let mut pending = Vec::new();
+    let mut skipped = 0;
+    for kind in ContentKind::ALL {
+        let dir = path.join(instance_dir(&project.manifest, kind));
+        let mut kind_sources: Vec<BuildSource> = sources
             .iter()
-            .position(|source| mod_entry.file_name() == OsString::from(&source.file))
-        {
-            let source = &sources[index];
-
-            if let Some(source_hash) = &source.sha512 {
-                let hash = sha512(&mod_entry.path()).wrap_err(format!(
-                    "failed to generate sha512 for mod `{}`",
-                    source.file
-                ))?;
-
-                if source_hash == &hash {
-                    sources.remove(index);
-                    continue;
-                }
+            .filter(|source| source.kind == kind)
+            .cloned()
+            .collect();
+
+        if !dir.exists() {
+            if kind != ContentKind::Mod && kind_sources.is_empty() {
+                continue;
             }
 
-            fs::remove_file(mod_entry.path())
-                .wrap_err(format!("failed to remove mod `{}`", &source.file))?;
+            fs::create_dir_all(&dir).wrap_err(format!(
+                "failed to create {} directory inside instance",
+                kind.as_str()
+            ))?;
+        }
+
+        skipped += remove_outdated(&dir, &mut kind_sources, previous.as_ref(), jobs)?;
+        pending.extend(kind_sources.into_iter().map(|source| {
+            let target = dir.join(&source.file);
+            (source, target)
+        }));
+    }
 
-            download_source(&client, source, &mod_entry.path())
-                .wrap_err(format!("failed to download mod `{}`", &source.file))?;
+    let installed: Vec<String> = sources
+        .iter()
+        .map(|source| {
+            format!(
+                "{}/{}",
+                instance_dir(&project.manifest, source.kind),
+                source.file
+            )
+        })
+        .collect();
+    fs::write(&installed_path, installed.join("\n")).wrap_err("failed to write installed files")?;
 
-            sources.remove(index);
-            continue;
+    // Copy the local mods, they don't need to go through the cache
+    let (local, pending): (Vec<_>, Vec<_>) = pending
+        .into_iter()
+        .partition(|(source, _)| source.path.is_some());
+
+    let mut copied = 0;
+    let mut copy_failed = Vec::new();
+    for (source, target) in &local {
+        let Some(from) = &source.path else { continue };
+        match fs::copy(root.join(from), target) {
+            Ok(_) => {
+                debug!("  {}: copied", &source.name);
+                copied += 1;
+            }
+            Err(err) => {
+                error!("failed to copy mod `{}`: {}", &source.name, err);
+                copy_failed.push(source.name.as_str());
+            }
         }
+    }
 
-        fs::remove_file(mod_entry.path()).wrap_err(format!(
-            "failed to remove mod `{}`",
-            mod_entry.file_name().to_string_lossy()
-        ))?;
+    let results = parallel::map(&pending, jobs, |(source, target)| {
+        download_source(&client, &cache, source, target)
+    });
+
+    let (mut downloaded, mut cached) = (0, 0);
+    let mut download_failed = Vec::new();
+    for ((source, _), result) in pending.iter().zip(results) {
+        match result {
+            Ok(Some(report)) => {
+                downloaded += 1;
+                if report.attempts > 1 || report.resumed {
+                    info!(
+                        "  {}: downloaded after {} attempts{}",
+                        &source.name,
+                        report.attempts,
+                        if report.resumed { ", resumed" } else { "" }
+                    );
+                } else {
+                    debug!("  {}: downloaded", &source.name);
+                }
+            }
+            Ok(None) => {
+                debug!("  {}: from cache", &source.name);
+                cached += 1;
+            }
+            Err(err) => {
+                error!("failed to download mod `{}`: {:#}", &source.name, err);
+                download_failed.push(source.name.as_str());
+            }
+        }
     }
 
+    info!(
+        "{} downloaded, {} copied, {} from cache, {} up to date, {} failed to download, {} failed to copy",
+        downloaded,
+        copied,
+        cached,
+        skipped,
+        download_failed.len(),
+        copy_failed.len()
+    );
+    for name in &download_failed {
+        info!("  {}: failed to download", name);
+    }
+    for name in &copy_failed {
+        info!("  {}: failed to copy", name);
+    }
+
+    ensure!(
+        download_failed.is_empty(),
+        "failed to download {} mods: {}",
+        download_failed.len(),
+        download_failed.join(", ")
+    );
+    ensure!(
+        copy_failed.is_empty(),
+        "failed to copy {} local mods: {}",
+        copy_failed.len(),
+        copy_failed.join(", ")
+    );
+
+    Ok(())
+}
+
+/// Checks that the file names of the sources can be used in paths, and that no two
+/// sources are installed to the same file.
+pub fn check_targets(manifest: &Manifest, sources: &[BuildSource]) -> Result<()> {
+    let mut targets: HashMap<String, &str> = HashMap::new();
     for source in sources {
-        download_source(&client, &source, &mods_dir.join(&source.file))
-            .wrap_err(format!("failed to download mod `{}`", &source.file))?;
+        source.check()?;
+
+        let target = format!("{}/{}", instance_dir(manifest, source.kind), source.file);
+        if let Some(other) = targets.insert(target.clone(), &source.name) {
+            bail!(
+                "mods `{}` and `{}` are both installed as `{}`",
+                other,
+                source.name,
+                target
+            );
+        }
     }
 
     Ok(())
 }
 
-fn sha512(path: &Path) -> Result<String> {
-    let mut file = File::open(path)?;
-    let mut sha512 = Sha512::new();
+/// Removes the files of a directory that don't match any source, and drops the sources
+/// of the files that are up to date.
+///
+/// Directories and files that aren't in `installed` are kept, unless `installed` is `None`.
+///
+/// Returns the number of files that are up to date.
+fn remove_outdated(
+    dir: &Path,
+    sources: &mut Vec<BuildSource>,
+    installed: Option<&HashSet<PathBuf>>,
+    jobs: usize,
+) -> Result<usize> {
+    let entries = fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<io::Result<Vec<_>>>()?
+        .into_iter()
+        .filter(|entry| entry.is_file())
+        .collect::<Vec<_>>();
+
+    let up_to_date = parallel::map(&entries, jobs, |mod_path| -> Result<bool> {
+        let Some(source) = sources
+            .iter()
+            .find(|source| mod_path.file_name() == Some(OsStr::new(&source.file)))
+        else {
+            return Ok(false);
+        };
+
+        match &source.sha512 {
+            Some(source_hash) => Ok(&hash::sha512(mod_path).wrap_err(format!(
+                "failed to generate sha512 for mod `{}`",
+                source.name
+            ))? == source_hash),
+            None => Ok(false),
+        }
+    });
 
-    io::copy(&mut file, &mut sha512)?;
+    let mut skipped = 0;
+    for (mod_path, up_to_date) in entries.iter().zip(up_to_date) {
+        if up_to_date? {
+            skipped += 1;
+            sources.retain(|source| {
+                if mod_path.file_name() != Some(OsStr::new(&source.file)) {
+                    return true;
+                }
 
-    Ok(hex::encode(sha512.finalize()))
+                debug!("  {}: up to date", source.name);
+                false
+            });
+            continue;
+        }
+
+        if installed.is_some_and(|installed| !installed.contains(mod_path)) {
+            continue;
+        }
+
+        fs::remove_file(mod_path).wrap_err(format!(
+            "failed to remove mod `{}`",
+            mod_path.file_name().unwrap_or_default().to_string_lossy()
+        ))?;
+    }
+
+    Ok(skipped)
 }
 
+/// Installs a mod from the cache, downloading it first if needed.
+///
+/// Returns a report if the mod was downloaded.
 fn download_source(
     client: &reqwest::blocking::Client,
+    cache: &Cache,
     source: &BuildSource,
     path: &Path,
-) -> Result<()> {
-    info!("Downloading {}", &source.file);
+) -> Result<Option<DownloadReport>> {
+    let url = source.url.as_deref().wrap_err("mod has no download url")?;
+    let (cached, report) = cache.get_or_insert(source, |part| {
+        info!("Downloading {}", &source.file);
+        let report = download::download(client, part, url)?;
+        verify(part, source)?;
+        Ok(report)
+    })?;
 
-    download(client, path, &source.url)?;
+    cache::install(&cached, path).wrap_err("failed to install mod from cache")?;
 
-    Ok(())
+    Ok(report)
 }
 
-fn download(client: &reqwest::blocking::Client, path: &Path, url: &str) -> Result<()> {
-    let response = client.get(url).send().wrap_err("failed to send request")?;
+/// Checks a downloaded file against the known hashes of the source.
+///
+/// The file is removed if it doesn't match.
+fn verify(path: &Path, source: &BuildSource) -> Result<()> {
+    let mismatch = hash::mismatch(path, source.sha512.as_deref(), source.sha1.as_deref())
+        .wrap_err("failed to hash download")?;
 
-    let body = response.bytes()?;
-    fs::write(path, &body).wrap_err(format!("failed to write to file `{:?}`", path))?;
+    if let Some((algorithm, expected, actual)) = mismatch {
+        fs::remove_file(path).wrap_err("failed to remove corrupted download")?;
+        bail!(
+            "{} mismatch for `{}`, expected `{}` but got `{}`",
+            algorithm,
+            source.file,
+            expected,
+            actual
+        );
+    }
 
     Ok(())
 }
 
-fn copy_recursive<U: AsRef<Path>, V: AsRef<Path>>(from: U, to: V) -> Result<()> {
+pub fn copy_recursive<U: AsRef<Path>, V: AsRef<Path>>(from: U, to: V) -> Result<()> {
     if !to.as_ref().exists() {
         fs::create_dir(&to)?;
     }
@@ -132,3 +365,106 @@ fn copy_recursive<U: AsRef<Path>, V: AsRef<Path>>(from: U, to: V) -> Result<()>
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn source(name: &str, file: &str, kind: ContentKind) -> BuildSource {
+        BuildSource {
+            name: name.to_string(),
+            project_id: None,
+            version_id: None,
+            version_number: None,
+            url: Some(format!("https://cdn.example.com/{}", file)),
+            path: None,
+            file: file.to_string(),
+            sha512: None,
+            sha1: None,
+            size: None,
+            kind,
+            dependencies: Vec::new(),
+        }
+    }
+
+    fn manifest() -> Manifest {
+        Manifest::new(
+            "pack".to_string(),
+            "1.0.0".to_string(),
+            Some("1.20.1".to_string()),
+            Some("fabric".to_string()),
+            None,
+        )
+    }
+
+    #[test]
+    fn rejects_duplicate_targets() {
+        let sources = [
+            source("a", "lib.jar", ContentKind::Mod),
+            source("b", "lib.jar", ContentKind::Mod),
+        ];
+
+        let err = check_targets(&manifest(), &sources).unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "mods `a` and `b` are both installed as `mods/lib.jar`"
+        );
+    }
+
+    #[test]
+    fn accepts_same_files_of_other_kinds() {
+        let sources = [
+            source("a", "pack.zip", ContentKind::ResourcePack),
+            source("b", "pack.zip", ContentKind::ShaderPack),
+        ];
+
+        check_targets(&manifest(), &sources).unwrap();
+    }
+
+    fn download(name: &str, contents: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "niterpack-verify-{}-{}.jar",
+            name,
+            std::process::id()
+        ));
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn accepts_matching_downloads() {
+        let path = download("matching", "mod");
+        let source = BuildSource {
+            sha512: Some(hash::sha512(&path).unwrap()),
+            sha1: Some("ignored when sha512 is known".to_string()),
+            ..source("a", "a.jar", ContentKind::Mod)
+        };
+
+        let result = verify(&path, &source);
+        fs::remove_file(&path).unwrap();
+
+        result.unwrap();
+    }
+
+    #[test]
+    fn removes_mismatching_downloads() {
+        let path = download("mismatching", "mod");
+        let expected = "0".repeat(40);
+        let source = BuildSource {
+            sha1: Some(expected.clone()),
+            ..source("a", "a.jar", ContentKind::Mod)
+        };
+
+        let err = verify(&path, &source).unwrap_err();
+
+        assert!(!path.exists());
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "sha1 mismatch for `a.jar`, expected `{}` but got `{}`",
+                expected, "7dd30f0a95d522bfc058be4e75847f8b6df9f76b"
+            )
+        );
+    }
+}
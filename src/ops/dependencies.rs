@@ -0,0 +1,397 @@
+use crate::source::BuildSource;
+use crate::util::modrinth::{self, DependencyType, ModrinthClient};
+use crate::{ContentKind, Manifest, Mod, Source};
+use eyre::{bail, ContextCompat, Result, WrapErr};
+use log::warn;
+use std::collections::{HashMap, HashSet};
+
+/// Collects the required dependencies of a Modrinth version, and their own required
+/// dependencies, that are missing from `mods`.
+pub fn required_dependencies(
+    client: &ModrinthClient,
+    manifest: &Manifest,
+    mods: &[Mod],
+    version: &modrinth::Version,
+) -> Result<Vec<Mod>> {
+    let mut added: Vec<Mod> = Vec::new();
+    let mut queue = vec![version.clone()];
+
+    while let Some(version) = queue.pop() {
+        for dependency in &version.dependencies {
+            if dependency.dependency_type != DependencyType::Required {
+                continue;
+            }
+
+            let (project, dependency_version) =
+                match (&dependency.project_id, &dependency.version_id) {
+                    (_, Some(version_id)) => {
+                        let dependency_version = client
+                            .version(version_id)
+                            .wrap_err("failed to fetch dependency version")?;
+                        let project = client
+                            .project(&dependency_version.project_id)
+                            .wrap_err("failed to fetch dependency project")?;
+                        (project, Some(dependency_version))
+                    }
+                    (Some(project_id), None) => (
+                        client
+                            .project(project_id)
+                            .wrap_err("failed to fetch dependency project")?,
+                        None,
+                    ),
+                    (None, None) => continue,
+                };
+
+            if mods
+                .iter()
+                .chain(added.iter())
+                .any(|mod_data| mod_data.name == project.slug || mod_data.name == project.id)
+            {
+                continue;
+            }
+
+            let kind = ContentKind::from_project_type(&project.project_type).unwrap_or_default();
+            let dependency_version = match dependency_version {
+                Some(dependency_version) => dependency_version,
+                None => client
+                    .project_versions(
+                        &project.id,
+                        kind.loaders(manifest).as_deref(),
+                        manifest.minecraft_version.as_deref(),
+                    )
+                    .wrap_err("failed to fetch dependency versions")?
+                    .into_iter()
+                    .next()
+                    .wrap_err(format!(
+                        "dependency `{}` doesn't have a valid version for this modpack",
+                        project.slug
+                    ))?,
+            };
+
+            added.push(
+                Mod::new(
+                    project.slug,
+                    None,
+                    None,
+                    Source::Modrinth {
+                        version: dependency_version.version_number.clone(),
+                    },
+                    kind,
+                )
+                .with_support(project.client_side, project.server_side),
+            );
+            queue.push(dependency_version);
+        }
+    }
+
+    Ok(added)
+}
+
+/// Ensures that every required dependency of the resolved mods is part of the modpack.
+///
+/// Only Modrinth mods can satisfy dependencies. If `force` is set, missing dependencies
+/// are only reported as warnings, so that they can be provided by other sources.
+pub fn check_dependencies(
+    client: &ModrinthClient,
+    sources: &[BuildSource],
+    force: bool,
+) -> Result<()> {
+    let project_ids: HashSet<&str> = sources
+        .iter()
+        .filter_map(|source| source.project_id.as_deref())
+        .collect();
+    let version_ids: HashSet<&str> = sources
+        .iter()
+        .filter_map(|source| source.version_id.as_deref())
+        .collect();
+
+    let mut unresolved = Vec::new();
+    for source in sources {
+        for dependency in &source.dependencies {
+            if dependency.dependency_type != DependencyType::Required {
+                continue;
+            }
+
+            if dependency
+                .project_id
+                .as_deref()
+                .is_some_and(|id| project_ids.contains(id))
+                || dependency
+                    .version_id
+                    .as_deref()
+                    .is_some_and(|id| version_ids.contains(id))
+            {
+                continue;
+            }
+
+            if dependency.project_id.is_some() || dependency.version_id.is_some() {
+                unresolved.push((source, dependency));
+            }
+        }
+    }
+
+    if unresolved.is_empty() {
+        return Ok(());
+    }
+
+    // Find the projects of dependencies only referencing a version
+    let dependency_versions: Vec<&str> = unresolved
+        .iter()
+        .filter(|(_, dependency)| dependency.project_id.is_none())
+        .filter_map(|(_, dependency)| dependency.version_id.as_deref())
+        .collect();
+    let version_projects: HashMap<String, String> = if dependency_versions.is_empty() {
+        HashMap::new()
+    } else {
+        client
+            .versions(&dependency_versions)
+            .wrap_err("failed to fetch dependency versions")?
+            .into_iter()
+            .map(|version| (version.id, version.project_id))
+            .collect()
+    };
+
+    let mut missing = Vec::new();
+    for (source, dependency) in unresolved {
+        let project_id = match (&dependency.project_id, &dependency.version_id) {
+            (Some(project_id), _) => project_id.clone(),
+            (None, Some(version_id)) => version_projects
+                .get(version_id)
+                .cloned()
+                .wrap_err(format!("dependency version `{}` not found", version_id))?,
+            (None, None) => continue,
+        };
+
+        if !project_ids.contains(project_id.as_str()) {
+            missing.push((source, project_id));
+        }
+    }
+
+    if missing.is_empty() {
+        return Ok(());
+    }
+
+    // Fetch the names of the missing projects for the error message
+    let missing_ids: Vec<&str> = missing
+        .iter()
+        .map(|(_, project_id)| project_id.as_str())
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+    let names: HashMap<String, String> = client
+        .projects(&missing_ids)
+        .map(|projects| {
+            projects
+                .into_iter()
+                .map(|project| (project.id, project.slug))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let missing: Vec<String> = missing
+        .iter()
+        .map(|(source, project_id)| {
+            format!(
+                "`{}` requires `{}`",
+                source.name,
+                names.get(project_id).unwrap_or(project_id)
+            )
+        })
+        .collect();
+
+    if force {
+        for missing in &missing {
+            warn!("missing required dependency: {}", missing);
+        }
+        return Ok(());
+    }
+
+    bail!(
+        "missing required dependencies: {} (use `--force` to ignore)",
+        missing.join(", ")
+    );
+}
+
+/// Ensures that no resolved mod declares itself incompatible with another one.
+///
+/// If `force` is set, the conflicts are only reported as warnings.
+pub fn check_incompatibilities(sources: &[BuildSource], force: bool) -> Result<()> {
+    let mut conflicts = Vec::new();
+    for source in sources {
+        for dependency in &source.dependencies {
+            if dependency.dependency_type != DependencyType::Incompatible {
+                continue;
+            }
+
+            for other in sources {
+                let conflicting = match (&dependency.version_id, &dependency.project_id) {
+                    (Some(version_id), _) => other.version_id.as_ref() == Some(version_id),
+                    (None, Some(project_id)) => other.project_id.as_ref() == Some(project_id),
+                    (None, None) => false,
+                };
+
+                if conflicting {
+                    conflicts.push(format!(
+                        "`{}` is incompatible with `{}` (declared by `{}` version `{}`)",
+                        source.name,
+                        other.name,
+                        source.name,
+                        source
+                            .version_number
+                            .as_deref()
+                            .or(source.version_id.as_deref())
+                            .unwrap_or("unknown")
+                    ));
+                }
+            }
+        }
+    }
+
+    if force {
+        for conflict in &conflicts {
+            warn!("{}", conflict);
+        }
+    } else if !conflicts.is_empty() {
+        bail!(
+            "incompatible mods: {} (use `--force` to ignore)",
+            conflicts.join(", ")
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::modrinth::Dependency;
+
+    fn source(name: &str, project_id: Option<&str>, dependencies: Vec<Dependency>) -> BuildSource {
+        BuildSource {
+            name: name.to_string(),
+            project_id: project_id.map(str::to_string),
+            version_id: project_id.map(|id| id.to_lowercase()),
+            version_number: project_id.map(|_| "1.0.0".to_string()),
+            url: Some(format!("https://cdn.example.com/{}.jar", name)),
+            path: None,
+            file: format!("{}.jar", name),
+            sha512: None,
+            sha1: None,
+            size: None,
+            kind: ContentKind::Mod,
+            dependencies,
+        }
+    }
+
+    fn dependency(project_id: &str, dependency_type: DependencyType) -> Dependency {
+        Dependency {
+            version_id: None,
+            project_id: Some(project_id.to_string()),
+            file_name: None,
+            dependency_type,
+        }
+    }
+
+    // Satisfied dependencies need no request, missing ones fall back to their ids
+    fn client() -> ModrinthClient {
+        ModrinthClient::new("http://127.0.0.1:9".to_string(), "test", None).unwrap()
+    }
+
+    #[test]
+    fn accepts_satisfied_dependencies() {
+        let sources = [
+            source(
+                "a",
+                Some("AAAAAAAA"),
+                vec![
+                    dependency("LIBLIBLI", DependencyType::Required),
+                    dependency("OPTOPTOP", DependencyType::Optional),
+                ],
+            ),
+            source("lib", Some("LIBLIBLI"), Vec::new()),
+        ];
+
+        check_dependencies(&client(), &sources, false).unwrap();
+    }
+
+    #[test]
+    fn rejects_missing_dependencies() {
+        let sources = [
+            source(
+                "a",
+                Some("AAAAAAAA"),
+                vec![dependency("LIBLIBLI", DependencyType::Required)],
+            ),
+            source("lib", None, Vec::new()),
+        ];
+
+        let err = check_dependencies(&client(), &sources, false).unwrap_err();
+
+        assert!(err.to_string().contains("`a` requires `LIBLIBLI`"));
+    }
+
+    #[test]
+    fn forces_missing_dependencies() {
+        let sources = [source(
+            "a",
+            Some("AAAAAAAA"),
+            vec![dependency("LIBLIBLI", DependencyType::Required)],
+        )];
+
+        check_dependencies(&client(), &sources, true).unwrap();
+    }
+
+    #[test]
+    fn rejects_incompatible_mods() {
+        let sources = [
+            source(
+                "a",
+                Some("AAAAAAAA"),
+                vec![dependency("BBBBBBBB", DependencyType::Incompatible)],
+            ),
+            source("b", Some("BBBBBBBB"), Vec::new()),
+        ];
+
+        let err = check_incompatibilities(&sources, false).unwrap_err();
+
+        assert!(err
+            .to_string()
+            .contains("`a` is incompatible with `b` (declared by `a` version `1.0.0`)"));
+    }
+
+    #[test]
+    fn matches_incompatible_versions() {
+        let incompatible = Dependency {
+            version_id: Some("bbbbbbbb".to_string()),
+            ..dependency("BBBBBBBB", DependencyType::Incompatible)
+        };
+        let sources = [
+            source("a", Some("AAAAAAAA"), vec![incompatible.clone()]),
+            source("b", Some("BBBBBBBB"), Vec::new()),
+        ];
+        let other_version = [
+            source("a", Some("AAAAAAAA"), vec![incompatible]),
+            BuildSource {
+                version_id: Some("cccccccc".to_string()),
+                ..source("b", Some("BBBBBBBB"), Vec::new())
+            },
+        ];
+
+        assert!(check_incompatibilities(&sources, false).is_err());
+        check_incompatibilities(&other_version, false).unwrap();
+    }
+
+    #[test]
+    fn forces_incompatible_mods() {
+        let sources = [
+            source(
+                "a",
+                Some("AAAAAAAA"),
+                vec![dependency("BBBBBBBB", DependencyType::Incompatible)],
+            ),
+            source("b", Some("BBBBBBBB"), Vec::new()),
+        ];
+
+        check_incompatibilities(&sources, true).unwrap();
+    }
+}
@@ -0,0 +1,419 @@
+use super::copy_recursive;
+use crate::toml::JoinToml;
+use crate::util::modrinth::{self, ModrinthClient};
+use crate::util::mrpack::{self, Index, IndexFile};
+use crate::util::{hash, mmc};
+use crate::{ContentKind, Manifest, Mod, Project, Side, Source};
+use eyre::{ensure, ContextCompat, Result, WrapErr};
+use log::{info, warn};
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsStr;
+use std::fs::{self, File};
+use std::io;
+use std::path::Path;
+use url::Url;
+use zip::ZipArchive;
+
+pub fn import_mrpack<U: AsRef<Path>, V: AsRef<Path>>(
+    client: &ModrinthClient,
+    file: U,
+    path: V,
+) -> Result<Project> {
+    let mut zip = ZipArchive::new(File::open(file).wrap_err("failed to open mrpack file")?)
+        .wrap_err("failed to read mrpack file")?;
+
+    let index: Index = serde_json::from_reader(
+        zip.by_name(mrpack::INDEX_FILE)
+            .wrap_err(format!("could not find `{}`", mrpack::INDEX_FILE))?,
+    )
+    .wrap_err("failed to parse mrpack index")?;
+
+    let files: Vec<(&IndexFile, ContentKind)> = index
+        .files
+        .iter()
+        .filter_map(|file| {
+            let kind = ContentKind::ALL.into_iter().find(|kind| {
+                file.path
+                    .strip_prefix(kind.dir_name())
+                    .is_some_and(|path| path.starts_with('/'))
+            });
+            if kind.is_none() {
+                warn!(
+                    "skipping `{}`, only mods, resource packs, shader packs, datapacks and plugins can be imported",
+                    &file.path
+                );
+            }
+            Some((file, kind?))
+        })
+        .collect();
+
+    // Identify the files hosted on Modrinth in bulk
+    let hashes: Vec<&str> = files
+        .iter()
+        .filter(|(file, _)| is_modrinth_file(file))
+        .map(|(file, _)| file.hashes.sha512.as_str())
+        .collect();
+    let identified = identify(client, &hashes)?;
+
+    let mut mods = Vec::new();
+    for (file, kind) in files {
+        let modrinth = identified
+            .get(&file.hashes.sha512)
+            .map(|(project, version)| (project, version));
+
+        mods.push(
+            import_file(file, kind, modrinth)
+                .wrap_err(format!("failed to import `{}`", &file.path))?,
+        );
+    }
+
+    // Unpack the overrides, content files are added to the modpack as local files
+    let config_dir = path.as_ref().join_config_dir();
+    let server_config_dir = path.as_ref().join_server_config_dir();
+    let mut skipped = Vec::new();
+    for index in 0..zip.len() {
+        let mut entry = zip.by_index(index)?;
+        let name = entry
+            .enclosed_name()
+            .wrap_err(format!("invalid file path `{}`", entry.name()))?
+            .to_string_lossy()
+            .replace('\\', "/");
+
+        if name == mrpack::INDEX_FILE || entry.is_dir() {
+            continue;
+        }
+
+        let (root, relative) = name.split_once('/').unwrap_or((&name, ""));
+        let side = match root {
+            mrpack::OVERRIDES_DIR => None,
+            mrpack::CLIENT_OVERRIDES_DIR => Some(Side::Client),
+            mrpack::SERVER_OVERRIDES_DIR => Some(Side::Server),
+            _ => {
+                skipped.push(name);
+                continue;
+            }
+        };
+
+        let content = ContentKind::ALL.into_iter().find_map(|kind| {
+            let file_name = relative
+                .strip_prefix(kind.dir_name())?
+                .strip_prefix('/')
+                .filter(|file_name| !file_name.contains('/'))?;
+            Some((kind, file_name))
+        });
+
+        let target = if let Some((kind, file_name)) = content {
+            mods.push(
+                import_override(kind, file_name, side)
+                    .wrap_err(format!("failed to import `{}`", name))?,
+            );
+            path.as_ref().join_files_dir().join(file_name)
+        } else if side == Some(Side::Server) {
+            // Server overrides are copied over the server like the server config
+            server_config_dir.join(relative)
+        } else if let Some(relative) = relative.strip_prefix("config/") {
+            config_dir.join(relative)
+        } else {
+            skipped.push(name);
+            continue;
+        };
+
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)
+                .wrap_err(format!("failed to create `{}`", parent.display()))?;
+        }
+
+        io::copy(&mut entry, &mut File::create(&target)?)
+            .wrap_err(format!("failed to unpack `{}`", name))?;
+    }
+
+    if !skipped.is_empty() {
+        warn!(
+            "skipped {} override files, only configs, server files and content files can be imported: {}",
+            skipped.len(),
+            skipped
+                .iter()
+                .map(|name| format!("`{}`", name))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+
+    let loader = index
+        .dependencies
+        .iter()
+        .find_map(|(key, version)| Some((mrpack::dependency_loader(key)?, version)));
+
+    let manifest = Manifest::new(
+        index.name.clone(),
+        index.version_id.clone(),
+        index.dependencies.get(mrpack::GAME).cloned(),
+        loader.map(|(loader, _)| loader.to_string()),
+        loader.map(|(_, version)| version.clone()),
+    );
+
+    Ok(Project::new(
+        manifest,
+        mods,
+        Some(config_dir).filter(|path| path.exists()),
+        Some(server_config_dir).filter(|path| path.exists()),
+    ))
+}
+
+/// Creates a modpack from the mods and configuration files of an existing instance.
+///
+/// Mods are identified on Modrinth using their hashes, the ones that can't be identified
+/// are reported and skipped. The loader and Minecraft version are read from the
+/// `mmc-pack.json` file if it exists.
+pub fn import_instance<U: AsRef<Path>, V: AsRef<Path>>(
+    client: &ModrinthClient,
+    dir: U,
+    path: V,
+) -> Result<Project> {
+    let dir = dir.as_ref();
+    ensure!(
+        dir.is_dir(),
+        "instance directory `{}` not found",
+        dir.display()
+    );
+
+    let game_dir = mmc::GAME_DIRS
+        .iter()
+        .map(|name| dir.join(name))
+        .find(|game_dir| game_dir.is_dir())
+        .unwrap_or_else(|| dir.to_path_buf());
+
+    // Hash the jars to identify them on Modrinth in bulk
+    let mods_dir = game_dir.join_mods_dir();
+    let mut jars = Vec::new();
+    if mods_dir.is_dir() {
+        for entry in fs::read_dir(&mods_dir).wrap_err("failed to read mods directory")? {
+            let jar = entry?.path();
+            if jar.is_file() && jar.extension().and_then(OsStr::to_str) == Some("jar") {
+                jars.push(jar);
+            }
+        }
+    }
+    jars.sort();
+
+    let hashes = jars
+        .iter()
+        .map(|jar| {
+            hash::sha512(jar).wrap_err(format!("failed to generate sha512 for `{}`", jar.display()))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let identified = identify(
+        client,
+        &hashes.iter().map(String::as_str).collect::<Vec<_>>(),
+    )?;
+
+    let mut mods: Vec<Mod> = Vec::new();
+    let mut unidentified = Vec::new();
+    for (jar, hash) in jars.iter().zip(&hashes) {
+        let file_name = jar.file_name().unwrap_or_default().to_string_lossy();
+        let Some((project, version)) = identified.get(hash) else {
+            unidentified.push(file_name);
+            continue;
+        };
+
+        if mods.iter().any(|mod_data| mod_data.name == project.slug) {
+            warn!(
+                "skipping `{}`, `{}` was already imported",
+                file_name, project.slug
+            );
+            continue;
+        }
+
+        // The jar was found by its hash, so the exact version it belongs to is pinned
+        info!("Imported mod `{}`", &project.slug);
+        mods.push(
+            Mod::new(
+                project.slug.clone(),
+                None,
+                None,
+                Source::Modrinth {
+                    version: version.id.clone(),
+                },
+                ContentKind::Mod,
+            )
+            .with_support(project.client_side, project.server_side),
+        );
+    }
+
+    for file_name in &unidentified {
+        warn!("could not identify `{}` on Modrinth", file_name);
+    }
+    if !unidentified.is_empty() {
+        warn!(
+            "{} mods were not imported, use `niter add --file` to add them by hand",
+            unidentified.len()
+        );
+    }
+
+    // Copy the configuration files
+    let config_dir = path.as_ref().join_config_dir();
+    let instance_config = game_dir.join_config_dir();
+    if instance_config.is_dir() {
+        copy_recursive(instance_config, &config_dir).wrap_err("failed to copy config files")?;
+    }
+
+    let pack_file = dir.join(mmc::PACK_FILE);
+    let pack: Option<mmc::Pack> = if pack_file.is_file() {
+        Some(
+            serde_json::from_str(
+                &fs::read_to_string(&pack_file)
+                    .wrap_err(format!("failed to read `{}`", mmc::PACK_FILE))?,
+            )
+            .wrap_err(format!("failed to parse `{}`", mmc::PACK_FILE))?,
+        )
+    } else {
+        None
+    };
+    let components = pack.map(|pack| pack.components).unwrap_or_default();
+
+    let minecraft_version = components
+        .iter()
+        .find(|component| component.uid == mmc::MINECRAFT)
+        .and_then(|component| component.version.clone());
+    let loader = components.iter().find_map(|component| {
+        Some((
+            mmc::component_loader(&component.uid)?,
+            component.version.clone(),
+        ))
+    });
+
+    let manifest = Manifest::new(
+        dir.file_name()
+            .and_then(|name| name.to_str())
+            .wrap_err("failed to get name of the instance directory")?
+            .to_string(),
+        String::from("0.1.0"),
+        minecraft_version,
+        loader.as_ref().map(|(loader, _)| loader.to_string()),
+        loader.and_then(|(_, version)| version),
+    );
+
+    Ok(Project::new(
+        manifest,
+        mods,
+        Some(config_dir).filter(|path| path.exists()),
+        None,
+    ))
+}
+
+/// Looks up files on Modrinth by their sha512 hashes, along with their projects.
+fn identify(
+    client: &ModrinthClient,
+    hashes: &[&str],
+) -> Result<HashMap<String, (modrinth::Project, modrinth::Version)>> {
+    if hashes.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let versions = client
+        .version_files(hashes, "sha512")
+        .wrap_err("failed to fetch modrinth versions")?;
+    let project_ids: Vec<&str> = versions
+        .values()
+        .map(|version| version.project_id.as_str())
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+    let projects: HashMap<String, modrinth::Project> = client
+        .projects(&project_ids)
+        .wrap_err("failed to fetch modrinth projects")?
+        .into_iter()
+        .map(|project| (project.id.clone(), project))
+        .collect();
+
+    Ok(versions
+        .into_iter()
+        .filter_map(|(hash, version)| {
+            let project = projects.get(&version.project_id)?.clone();
+            Some((hash, (project, version)))
+        })
+        .collect())
+}
+
+fn is_modrinth_file(file: &IndexFile) -> bool {
+    file.downloads.first().is_some_and(|url| {
+        Url::parse(url).is_ok_and(|url| url.host_str() == Some(mrpack::MODRINTH_CDN))
+    })
+}
+
+/// Creates a local file source for a content file unpacked from the overrides.
+fn import_override(kind: ContentKind, file_name: &str, side: Option<Side>) -> Result<Mod> {
+    let name = Path::new(file_name)
+        .file_stem()
+        .and_then(|name| name.to_str())
+        .wrap_err("invalid file name")?
+        .to_string();
+
+    info!("Imported local {} `{}`", kind.as_str(), &name);
+    Ok(Mod::new(
+        name,
+        None,
+        side,
+        Source::Path {
+            path: Path::new("")
+                .join_files_dir()
+                .join(file_name)
+                .to_str()
+                .wrap_err("invalid file path")?
+                .replace('\\', "/"),
+        },
+        kind,
+    ))
+}
+
+fn import_file(
+    file: &IndexFile,
+    kind: ContentKind,
+    modrinth: Option<(&modrinth::Project, &modrinth::Version)>,
+) -> Result<Mod> {
+    let url = file.downloads.first().wrap_err("file has no downloads")?;
+
+    if let Some((project, version)) = modrinth {
+        info!("Imported {} `{}`", kind.as_str(), &project.slug);
+        let (client, server) = file
+            .env
+            .as_ref()
+            .map_or((project.client_side, project.server_side), |env| {
+                (env.client, env.server)
+            });
+        // The file was found by its hash, so the exact version it belongs to is pinned
+        return Ok(Mod::new(
+            project.slug.clone(),
+            None,
+            None,
+            Source::Modrinth {
+                version: version.id.clone(),
+            },
+            kind,
+        )
+        .with_support(client, server));
+    }
+
+    let name = Path::new(&file.path)
+        .file_stem()
+        .and_then(|name| name.to_str())
+        .wrap_err("invalid file name")?
+        .to_string();
+
+    info!("Imported {} `{}`", kind.as_str(), &name);
+    let mod_data = Mod::new(
+        name,
+        None,
+        None,
+        Source::Download {
+            url: url.clone(),
+            sha512: Some(file.hashes.sha512.clone()),
+            sha1: Some(file.hashes.sha1.clone()),
+        },
+        kind,
+    );
+    Ok(match &file.env {
+        Some(env) => mod_data.with_support(env.client, env.server),
+        None => mod_data,
+    })
+}
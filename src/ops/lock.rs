@@ -0,0 +1,38 @@
+use crate::toml::JoinToml;
+use crate::util::modrinth::ModrinthClient;
+use crate::{Lockfile, Project};
+use eyre::{ensure, Result, WrapErr};
+use std::path::Path;
+
+/// Resolves the project against its lockfile, updating the lockfile when needed.
+///
+/// If `locked` is set, an error is returned instead of updating the lockfile.
+pub fn lock<P: AsRef<Path>>(
+    client: &ModrinthClient,
+    project: &Project,
+    path: P,
+    locked: bool,
+) -> Result<Lockfile> {
+    let lock_path = path.as_ref().join_lock_file();
+
+    let previous = if lock_path.exists() {
+        Some(Lockfile::read(&lock_path).wrap_err("failed to read lock file")?)
+    } else {
+        None
+    };
+
+    let lockfile = Lockfile::resolve(client, project, previous.as_ref())?;
+
+    if previous.as_ref() != Some(&lockfile) {
+        ensure!(
+            !locked,
+            "`niterpack.lock` needs to be updated but `--locked` was passed"
+        );
+
+        lockfile
+            .write(&lock_path)
+            .wrap_err("failed to write lock file")?;
+    }
+
+    Ok(lockfile)
+}
@@ -1,3 +1,19 @@
 pub mod build;
+pub mod dependencies;
+pub mod import;
+pub mod lock;
+pub mod mrpack;
+pub mod outdated;
+pub mod search;
+pub mod server;
+pub mod update;
 
 pub use build::*;
+pub use dependencies::*;
+pub use import::*;
+pub use lock::*;
+pub use mrpack::*;
+pub use outdated::*;
+pub use search::*;
+pub use server::*;
+pub use update::*;
@@ -0,0 +1,366 @@
+use crate::cache::Cache;
+use crate::source::BuildSource;
+use crate::util::modrinth::SideSupport;
+use crate::util::mrpack::{self, Index, IndexEnv, IndexFile, IndexHashes};
+use crate::{ContentKind, Mod, Project, Side};
+use eyre::{ensure, ContextCompat, Result, WrapErr};
+use log::{info, warn};
+use sha1::Sha1;
+use sha2::{Digest, Sha512};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use zip::write::FileOptions;
+use zip::ZipWriter;
+
+pub fn build_mrpack(
+    project: &Project,
+    root: &Path,
+    sources: Vec<BuildSource>,
+    path: PathBuf,
+) -> Result<()> {
+    let file_name = format!(
+        "{}-{}.mrpack",
+        project.manifest.name, project.manifest.version
+    );
+    export_mrpack(project, root, sources, path.join(file_name))
+}
+
+/// Exports a modpack to a mrpack file, with the local files of the mods read from the
+/// project `root`.
+pub fn export_mrpack(
+    project: &Project,
+    root: &Path,
+    sources: Vec<BuildSource>,
+    path: PathBuf,
+) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        if !parent.exists() {
+            fs::create_dir_all(parent).wrap_err("failed to create build directory")?;
+        }
+    }
+
+    let client = reqwest::blocking::Client::builder()
+        .build()
+        .wrap_err("failed to create a reqwest client")?;
+    let cache = Cache::open()?;
+
+    let mut files = Vec::new();
+    let mut overrides = Vec::new();
+    super::check_targets(&project.manifest, &sources)?;
+    for source in sources {
+        let mod_data = project
+            .mods
+            .iter()
+            .find(|mod_data| mod_data.name == source.name && mod_data.kind == source.kind);
+        let (side, support) = match source.kind {
+            // Plugins are only used by servers
+            ContentKind::Plugin if project.manifest.supports_plugins() => {
+                (Some(Side::Server), Some(Side::Server.support()))
+            }
+            ContentKind::Plugin => {
+                warn!(
+                    "skipping plugin `{}`, loader `{}` can't run plugins",
+                    source.name,
+                    project.manifest.loader.as_deref().unwrap_or("none")
+                );
+                continue;
+            }
+            _ => (
+                mod_data.and_then(|mod_data| mod_data.side),
+                mod_data.and_then(Mod::support),
+            ),
+        };
+
+        let dir = super::instance_dir(&project.manifest, source.kind);
+
+        // Local mods aren't hosted anywhere, so they are packed as overrides
+        if let Some(local) = source.path {
+            let overrides_dir = match side {
+                Some(Side::Client) => mrpack::CLIENT_OVERRIDES_DIR,
+                Some(Side::Server) => mrpack::SERVER_OVERRIDES_DIR,
+                _ => mrpack::OVERRIDES_DIR,
+            };
+            overrides.push((
+                root.join(local),
+                format!("{}/{}/{}", overrides_dir, dir, source.file),
+            ));
+            continue;
+        }
+
+        files.push(
+            index_file(&client, &cache, source.clone(), &dir, support)
+                .wrap_err(format!("failed to index mod `{}`", &source.name))?,
+        );
+    }
+
+    let index = Index {
+        format_version: mrpack::FORMAT_VERSION,
+        game: mrpack::GAME.into(),
+        version_id: project.manifest.version.clone(),
+        name: project.manifest.name.clone(),
+        summary: None,
+        files,
+        dependencies: dependencies(project),
+    };
+
+    let mut zip = ZipWriter::new(File::create(&path).wrap_err("failed to create mrpack file")?);
+    let options = FileOptions::default();
+
+    zip.start_file(mrpack::INDEX_FILE, options)?;
+    zip.write_all(serde_json::to_string_pretty(&index)?.as_bytes())?;
+
+    // Pack the configuration files as overrides
+    if let Some(project_config) = &project.config_dir {
+        zip_recursive(
+            &mut zip,
+            project_config,
+            &format!("{}/config", mrpack::OVERRIDES_DIR),
+        )
+        .wrap_err("failed to pack config files")?;
+    }
+
+    for (local, name) in overrides {
+        zip.start_file(&name, options)?;
+        io::copy(
+            &mut File::open(&local)
+                .wrap_err(format!("failed to open mod `{}`", local.display()))?,
+            &mut zip,
+        )
+        .wrap_err(format!("failed to pack mod `{}`", local.display()))?;
+    }
+
+    zip.finish()?;
+
+    info!("Exported modpack to {}", path.display());
+    Ok(())
+}
+
+fn dependencies(project: &Project) -> BTreeMap<String, String> {
+    let manifest = &project.manifest;
+    let mut dependencies = BTreeMap::new();
+
+    match &manifest.minecraft_version {
+        Some(version) => {
+            dependencies.insert(mrpack::GAME.into(), version.clone());
+        }
+        None => warn!("minecraft version is not defined, mrpack will not be installable"),
+    }
+
+    if let Some(loader) = &manifest.loader {
+        match (mrpack::loader_dependency(loader), &manifest.loader_version) {
+            (Some(key), Some(version)) => {
+                dependencies.insert(key.into(), version.clone());
+            }
+            (Some(_), None) => warn!(
+                "loader version is not defined, `{}` will not be included in mrpack dependencies",
+                loader
+            ),
+            (None, _) => warn!("loader `{}` is not supported by the mrpack format", loader),
+        }
+    }
+
+    dependencies
+}
+
+fn index_file(
+    client: &reqwest::blocking::Client,
+    cache: &Cache,
+    source: BuildSource,
+    dir: &str,
+    support: Option<(SideSupport, SideSupport)>,
+) -> Result<IndexFile> {
+    let url = source.url.as_deref().wrap_err("mod has no download url")?;
+
+    let (sha1, sha512, size) = match (&source.sha1, &source.sha512, source.size) {
+        (Some(sha1), Some(sha512), Some(size)) => (sha1.clone(), sha512.clone(), size),
+        _ => {
+            // Hash the cached file if the mod was built before, instead of downloading it again
+            let body = match cache.get(&source)? {
+                Some(path) => fs::read(path).wrap_err("failed to read cached mod")?,
+                None => {
+                    info!("Hashing {}", &source.file);
+                    client
+                        .get(url)
+                        .send()
+                        .wrap_err("failed to send request")?
+                        .error_for_status()?
+                        .bytes()?
+                        .to_vec()
+                }
+            };
+
+            let sha1 = hex::encode(Sha1::digest(&body));
+            let sha512 = hex::encode(Sha512::digest(&body));
+            if let Some(expected) = &source.sha512 {
+                ensure!(
+                    *expected == sha512,
+                    "sha512 mismatch for `{}`, expected `{}` but got `{}`",
+                    source.file,
+                    expected,
+                    sha512
+                );
+            }
+            if let Some(expected) = &source.sha1 {
+                ensure!(
+                    *expected == sha1,
+                    "sha1 mismatch for `{}`, expected `{}` but got `{}`",
+                    source.file,
+                    expected,
+                    sha1
+                );
+            }
+
+            (sha1, sha512, body.len() as u64)
+        }
+    };
+
+    Ok(IndexFile {
+        path: format!("{}/{}", dir, source.file),
+        hashes: IndexHashes { sha1, sha512 },
+        env: support.map(|(client, server)| IndexEnv { client, server }),
+        downloads: vec![url.to_string()],
+        file_size: size,
+    })
+}
+
+fn zip_recursive<W: Write + io::Seek, P: AsRef<Path>>(
+    zip: &mut ZipWriter<W>,
+    from: P,
+    prefix: &str,
+) -> Result<()> {
+    // Entries are sorted so that the archive is the same on every export
+    let mut entries = fs::read_dir(from)?.collect::<io::Result<Vec<_>>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+
+    for entry in entries {
+        let path = entry.path();
+        let name = format!("{}/{}", prefix, entry.file_name().to_string_lossy());
+        if path.is_dir() {
+            zip.add_directory(&name, FileOptions::default())?;
+            zip_recursive(zip, path, &name)?;
+        } else {
+            zip.start_file(&name, FileOptions::default())?;
+            io::copy(&mut File::open(path)?, zip)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Manifest, Source};
+    use std::env;
+    use zip::ZipArchive;
+
+    fn build_source(name: &str, url: Option<&str>, path: Option<&str>) -> BuildSource {
+        BuildSource {
+            name: name.to_string(),
+            project_id: None,
+            version_id: None,
+            version_number: None,
+            url: url.map(str::to_string),
+            path: path.map(str::to_string),
+            file: format!("{}.jar", name),
+            sha512: url.map(|_| "ab".repeat(64)),
+            sha1: url.map(|_| "cd".repeat(20)),
+            size: url.map(|_| 42),
+            kind: ContentKind::Mod,
+            dependencies: Vec::new(),
+        }
+    }
+
+    fn project(root: &Path) -> Project {
+        let manifest = Manifest::new(
+            "pack".to_string(),
+            "1.0.0".to_string(),
+            Some("1.20.1".to_string()),
+            Some("fabric".to_string()),
+            Some("0.14.21".to_string()),
+        );
+
+        let config = root.join("config");
+        fs::create_dir_all(config.join("b")).unwrap();
+        fs::write(config.join("b").join("b.toml"), "b").unwrap();
+        fs::write(config.join("a.toml"), "a").unwrap();
+
+        let lib = Mod::new(
+            "lib".to_string(),
+            None,
+            None,
+            Source::Download {
+                url: "https://cdn.modrinth.com/lib.jar".to_string(),
+                sha512: None,
+                sha1: None,
+            },
+            ContentKind::Mod,
+        )
+        .with_support(SideSupport::Required, SideSupport::Optional);
+        let local = Mod::new(
+            "local".to_string(),
+            None,
+            Some(Side::Server),
+            Source::Path {
+                path: "files/local.jar".to_string(),
+            },
+            ContentKind::Mod,
+        );
+
+        Project::new(manifest, vec![lib, local], Some(config), None)
+    }
+
+    fn export(root: &Path, name: &str) -> Vec<u8> {
+        let path = root.join(name);
+        export_mrpack(
+            &project(root),
+            root,
+            vec![
+                build_source("lib", Some("https://cdn.modrinth.com/lib.jar"), None),
+                build_source("local", None, Some("files/local.jar")),
+            ],
+            path.clone(),
+        )
+        .unwrap();
+        fs::read(path).unwrap()
+    }
+
+    #[test]
+    fn exports_index_and_overrides() {
+        let root = env::temp_dir().join(format!("niterpack-mrpack-{}", std::process::id()));
+        fs::create_dir_all(root.join("files")).unwrap();
+        fs::write(root.join("files").join("local.jar"), "local").unwrap();
+
+        let first = export(&root, "first.mrpack");
+        let second = export(&root, "second.mrpack");
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(first, second);
+
+        let mut archive = ZipArchive::new(io::Cursor::new(first)).unwrap();
+        let index: Index =
+            serde_json::from_reader(archive.by_name(mrpack::INDEX_FILE).unwrap()).unwrap();
+        assert_eq!(
+            index.dependencies.into_iter().collect::<Vec<_>>(),
+            vec![
+                ("fabric-loader".to_string(), "0.14.21".to_string()),
+                ("minecraft".to_string(), "1.20.1".to_string()),
+            ]
+        );
+        assert_eq!(index.files.len(), 1);
+        assert_eq!(index.files[0].path, "mods/lib.jar");
+        assert_eq!(index.files[0].file_size, 42);
+        let env = index.files[0].env.as_ref().unwrap();
+        assert_eq!(
+            (env.client, env.server),
+            (SideSupport::Required, SideSupport::Optional)
+        );
+
+        let names: Vec<&str> = archive.file_names().collect();
+        assert!(names.contains(&"server-overrides/mods/local.jar"));
+        assert!(names.contains(&"overrides/config/a.toml"));
+        assert!(names.contains(&"overrides/config/b/b.toml"));
+    }
+}
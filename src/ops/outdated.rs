@@ -0,0 +1,120 @@
+use crate::util::modrinth::{self, ModrinthClient};
+use crate::{Manifest, Mod, Source};
+use eyre::{Result, WrapErr};
+
+pub struct Outdated {
+    pub name: String,
+    pub current: String,
+    pub compatible: Option<modrinth::Version>,
+    pub latest: Option<modrinth::Version>,
+    pub update: Option<super::Update>,
+}
+
+impl Outdated {
+    /// Whether the latest version is newer than the latest compatible one, but doesn't
+    /// support the manifest's Minecraft version.
+    ///
+    /// The latest version can also be a backport to an older Minecraft version.
+    pub fn other_minecraft_only(&self, manifest: &Manifest) -> bool {
+        let (Some(latest), Some(minecraft_version)) = (&self.latest, &manifest.minecraft_version)
+        else {
+            return false;
+        };
+
+        let newer = match &self.compatible {
+            Some(compatible) => latest.date_published > compatible.date_published,
+            None => latest.version_number != self.current,
+        };
+
+        newer && !latest.game_versions.contains(minecraft_version)
+    }
+}
+
+/// Compares a Modrinth mod against its newest versions.
+///
+/// Returns `None` if the mod isn't a Modrinth mod.
+pub fn outdated(
+    client: &ModrinthClient,
+    manifest: &Manifest,
+    mod_data: &Mod,
+) -> Result<Option<Outdated>> {
+    let Source::Modrinth { version } = &mod_data.source else {
+        return Ok(None);
+    };
+
+    let compatible = client
+        .project_versions(
+            &mod_data.name,
+            mod_data.kind.loaders(manifest).as_deref(),
+            manifest.minecraft_version.as_deref(),
+        )
+        .wrap_err("failed to fetch modrinth project versions")?;
+
+    let latest = client
+        .project_versions(&mod_data.name, None, None)
+        .wrap_err("failed to fetch modrinth project versions")?;
+
+    Ok(Some(Outdated {
+        name: mod_data.name.clone(),
+        current: version.clone(),
+        update: super::newer_version(client, &mod_data.name, version, &compatible)?,
+        compatible: compatible.into_iter().next(),
+        latest: latest.into_iter().next(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn version(number: &str, game_version: &str, day: u32) -> modrinth::Version {
+        modrinth::Version {
+            id: number.to_string(),
+            project_id: "AABBCCDD".to_string(),
+            name: number.to_string(),
+            version_number: number.to_string(),
+            date_published: Utc.with_ymd_and_hms(2023, 6, day, 0, 0, 0).unwrap(),
+            game_versions: vec![game_version.to_string()],
+            loaders: vec!["fabric".to_string()],
+            files: Vec::new(),
+            dependencies: Vec::new(),
+        }
+    }
+
+    fn outdated(compatible: modrinth::Version, latest: modrinth::Version) -> Outdated {
+        Outdated {
+            name: "mod".to_string(),
+            current: compatible.version_number.clone(),
+            compatible: Some(compatible),
+            latest: Some(latest),
+            update: None,
+        }
+    }
+
+    fn manifest() -> Manifest {
+        Manifest::new(
+            "pack".to_string(),
+            "1.0.0".to_string(),
+            Some("1.20.1".to_string()),
+            Some("fabric".to_string()),
+            None,
+        )
+    }
+
+    #[test]
+    fn flags_versions_for_other_minecraft_versions() {
+        let newer = outdated(version("1.0.0", "1.20.1", 1), version("1.1.0", "1.20.2", 2));
+        let backport = outdated(version("1.0.0", "1.20.1", 1), version("0.9.1", "1.19.4", 2));
+
+        assert!(newer.other_minecraft_only(&manifest()));
+        assert!(backport.other_minecraft_only(&manifest()));
+    }
+
+    #[test]
+    fn does_not_flag_compatible_versions() {
+        let compatible = version("1.0.0", "1.20.1", 1);
+
+        assert!(!outdated(compatible.clone(), compatible).other_minecraft_only(&manifest()));
+    }
+}
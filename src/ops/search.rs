@@ -0,0 +1,35 @@
+use crate::util::modrinth::{self, ModrinthClient, ProjectType};
+use crate::{ContentKind, Manifest};
+use eyre::{Result, WrapErr};
+
+/// Searches Modrinth for projects of a type, compatible with the manifest if there is one.
+pub fn search(
+    client: &ModrinthClient,
+    manifest: Option<&Manifest>,
+    query: &str,
+    project_type: &ProjectType,
+    limit: usize,
+) -> Result<Vec<modrinth::SearchHit>> {
+    let mut facets = vec![vec![format!("project_type:{}", project_type.as_str())]];
+    if let Some(manifest) = manifest {
+        // Packs use their own loaders, they aren't filtered by the modpack one
+        if let Some(loaders) =
+            ContentKind::from_project_type(project_type).and_then(|kind| kind.loaders(manifest))
+        {
+            facets.push(
+                loaders
+                    .iter()
+                    .map(|loader| format!("categories:{}", loader))
+                    .collect(),
+            );
+        }
+        if let Some(version) = &manifest.minecraft_version {
+            facets.push(vec![format!("versions:{}", version)]);
+        }
+    }
+
+    Ok(client
+        .search(query, &facets, limit)
+        .wrap_err("failed to search modrinth projects")?
+        .hits)
+}
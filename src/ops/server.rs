@@ -0,0 +1,160 @@
+use crate::source::BuildSource;
+use crate::{ContentKind, Project, Side};
+use eyre::{Result, WrapErr};
+use log::warn;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const DEFAULT_JAR: &str = "server.jar";
+
+pub fn build_server(
+    project: &Project,
+    root: &Path,
+    sources: Vec<BuildSource>,
+    path: PathBuf,
+    jobs: usize,
+) -> Result<()> {
+    let path = path.join("server");
+    let supports_plugins = project.manifest.supports_plugins();
+
+    // Resource packs and shader packs are only used by clients
+    let sources = project
+        .side_sources(sources, Side::Server)
+        .into_iter()
+        .filter(|source| match source.kind {
+            ContentKind::Mod | ContentKind::Datapack => true,
+            ContentKind::Plugin if supports_plugins => true,
+            ContentKind::Plugin => {
+                warn!(
+                    "skipping plugin `{}`, loader `{}` can't run plugins",
+                    source.name,
+                    project.manifest.loader.as_deref().unwrap_or("none")
+                );
+                false
+            }
+            ContentKind::ResourcePack | ContentKind::ShaderPack => false,
+        })
+        .collect();
+
+    super::build_instance(project, root, sources, path.clone(), jobs)?;
+
+    // Copy the server-only files over the instance
+    if let Some(server_config) = &project.server_config_dir {
+        super::copy_recursive(server_config, &path)
+            .wrap_err("failed to copy server config files")?;
+    }
+
+    let server = project.manifest.server.clone().unwrap_or_default();
+
+    if server.eula {
+        fs::write(path.join("eula.txt"), "eula=true\n").wrap_err("failed to write eula.txt")?;
+    }
+
+    if !server.properties.is_empty() {
+        write_properties(&path.join("server.properties"), &server.properties)
+            .wrap_err("failed to write server.properties")?;
+    }
+
+    if server.start_scripts {
+        let jar = server.jar.as_deref().unwrap_or(DEFAULT_JAR);
+        let memory = server
+            .memory
+            .as_ref()
+            .map(|memory| format!("-Xmx{} ", memory))
+            .unwrap_or_default();
+        let command = format!("java {}-jar {} nogui", memory, jar);
+
+        write_script(&path.join("start.sh"), &format!("#!/bin/sh\n{}\n", command))
+            .wrap_err("failed to write start.sh")?;
+        write_script(
+            &path.join("start.bat"),
+            &format!("@echo off\r\n{}\r\npause\r\n", command),
+        )
+        .wrap_err("failed to write start.bat")?;
+    }
+
+    Ok(())
+}
+
+/// Writes the properties, keeping existing ones that aren't overridden.
+fn write_properties(path: &Path, properties: &BTreeMap<String, String>) -> Result<()> {
+    let mut lines = Vec::new();
+    let mut remaining = properties.clone();
+
+    if path.exists() {
+        for line in fs::read_to_string(path)?.lines() {
+            let key = line.split_once('=').map(|(key, _)| key.trim());
+            match key.and_then(|key| remaining.remove_entry(key)) {
+                Some((key, value)) => lines.push(format!("{}={}", key, value)),
+                None => lines.push(line.to_string()),
+            }
+        }
+    }
+
+    for (key, value) in remaining {
+        lines.push(format!("{}={}", key, value));
+    }
+
+    fs::write(path, lines.join("\n") + "\n")?;
+    Ok(())
+}
+
+fn write_script(path: &Path, contents: &str) -> Result<()> {
+    fs::write(path, contents)?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o755))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    fn properties(entries: &[(&str, &str)]) -> BTreeMap<String, String> {
+        entries
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn writes_new_properties() {
+        let path = env::temp_dir().join(format!("niterpack-new-{}.properties", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        write_properties(
+            &path,
+            &properties(&[("motd", "Hi"), ("difficulty", "hard")]),
+        )
+        .unwrap();
+        let written = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(written, "difficulty=hard\nmotd=Hi\n");
+    }
+
+    #[test]
+    fn keeps_existing_properties() {
+        let path = env::temp_dir().join(format!(
+            "niterpack-existing-{}.properties",
+            std::process::id()
+        ));
+        fs::write(&path, "#Minecraft server properties\nmotd=Old\npvp=true\n").unwrap();
+
+        write_properties(&path, &properties(&[("motd", "New"), ("max-players", "5")])).unwrap();
+        let written = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            written,
+            "#Minecraft server properties\nmotd=New\npvp=true\nmax-players=5\n"
+        );
+    }
+}
@@ -0,0 +1,138 @@
+use crate::util::modrinth::{self, ModrinthClient};
+use crate::{Manifest, Mod, Source};
+use eyre::{ContextCompat, Result, WrapErr};
+
+/// A newer version of a Modrinth mod.
+pub struct Update {
+    pub version: modrinth::Version,
+    /// Whether the current version is pinned by its id rather than its number.
+    pub by_id: bool,
+}
+
+impl Update {
+    /// Returns the reference to pin the new version by, in the style of the current pin.
+    pub fn pin(&self) -> String {
+        if self.by_id {
+            self.version.id.clone()
+        } else {
+            self.version.version_number.clone()
+        }
+    }
+}
+
+/// Finds a newer version of a Modrinth mod that is compatible with the manifest.
+///
+/// Returns `None` if the mod isn't a Modrinth mod or is already up to date.
+pub fn find_update(
+    client: &ModrinthClient,
+    manifest: &Manifest,
+    mod_data: &Mod,
+) -> Result<Option<Update>> {
+    let Source::Modrinth { version } = &mod_data.source else {
+        return Ok(None);
+    };
+
+    let versions = client
+        .project_versions(
+            &mod_data.name,
+            mod_data.kind.loaders(manifest).as_deref(),
+            manifest.minecraft_version.as_deref(),
+        )
+        .wrap_err("failed to fetch modrinth project versions")?;
+
+    newer_version(client, &mod_data.name, version, &versions)
+}
+
+/// Finds a version newer than `version` in a list of versions sorted from the newest.
+pub fn newer_version(
+    client: &ModrinthClient,
+    project: &str,
+    version: &str,
+    versions: &[modrinth::Version],
+) -> Result<Option<Update>> {
+    let Some(latest) = versions.first() else {
+        return Ok(None);
+    };
+
+    // Versions are sorted from the newest, so anything before the current one is newer
+    if let Some((index, current)) = versions.iter().enumerate().find(|(_, modrinth_version)| {
+        modrinth_version.version_number == version || modrinth_version.id == version
+    }) {
+        return Ok((index > 0).then(|| Update {
+            version: latest.clone(),
+            by_id: current.id == version,
+        }));
+    }
+
+    // The current version isn't compatible with the manifest, compare the publish dates
+    let (current, by_id) = match client.version(version) {
+        Ok(current) => (current, true),
+        Err(_) => (
+            client
+                .project_versions(project, None, None)
+                .wrap_err("failed to fetch modrinth project versions")?
+                .into_iter()
+                .find(|modrinth_version| modrinth_version.version_number == version)
+                .wrap_err(format!("could not find version `{}`", version))?,
+            false,
+        ),
+    };
+
+    Ok(
+        (latest.date_published > current.date_published).then(|| Update {
+            version: latest.clone(),
+            by_id,
+        }),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(id: &str, number: &str) -> modrinth::Version {
+        modrinth::Version {
+            id: id.to_string(),
+            project_id: "AABBCCDD".to_string(),
+            name: number.to_string(),
+            version_number: number.to_string(),
+            date_published: Default::default(),
+            game_versions: Vec::new(),
+            loaders: Vec::new(),
+            files: Vec::new(),
+            dependencies: Vec::new(),
+        }
+    }
+
+    // Versions found in the list need no request, so no server is needed
+    fn client() -> ModrinthClient {
+        ModrinthClient::new("http://127.0.0.1:9".to_string(), "test", None).unwrap()
+    }
+
+    #[test]
+    fn keeps_the_pin_style() {
+        let versions = [version("NEWNEWNE", "2.0.0"), version("OLDOLDOL", "1.0.0")];
+
+        let by_number = newer_version(&client(), "mod", "1.0.0", &versions)
+            .unwrap()
+            .unwrap();
+        let by_id = newer_version(&client(), "mod", "OLDOLDOL", &versions)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(by_number.pin(), "2.0.0");
+        assert_eq!(by_id.pin(), "NEWNEWNE");
+    }
+
+    #[test]
+    fn finds_no_update_for_the_newest_version() {
+        let versions = [version("NEWNEWNE", "2.0.0"), version("OLDOLDOL", "1.0.0")];
+
+        assert!(newer_version(&client(), "mod", "2.0.0", &versions)
+            .unwrap()
+            .is_none());
+        assert!(newer_version(&client(), "mod", "NEWNEWNE", &versions)
+            .unwrap()
+            .is_none());
+    }
+}
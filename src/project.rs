@@ -1,13 +1,56 @@
 use crate::source::BuildSource;
+use crate::util::modrinth::{ProjectType, SideSupport};
 use crate::{Manifest, Source};
 use eyre::Result;
+use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
 
 #[derive(Debug, Clone)]
 pub struct Mod {
     pub name: String,
     pub file: Option<String>,
+    pub side: Option<Side>,
+    /// Sides on which the mod is optional instead of required.
+    pub optional: Option<Side>,
     pub source: Source,
+    /// Kind of the content, determined by the directory of the mod file.
+    pub kind: ContentKind,
+}
+
+/// Kind of content a mod file describes, each kind has its own directory.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ContentKind {
+    /// A mod, loaded by the mod loader
+    #[default]
+    Mod,
+
+    /// A resource pack
+    #[value(name = "resourcepack")]
+    ResourcePack,
+
+    /// A shader pack
+    #[value(name = "shaderpack")]
+    ShaderPack,
+
+    /// A datapack
+    Datapack,
+
+    /// A server plugin
+    Plugin,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Side {
+    /// Only needed on the client
+    Client,
+
+    /// Only needed on the server
+    Server,
+
+    /// Needed on both the client and the server
+    Both,
 }
 
 #[derive(Debug, Clone)]
@@ -15,27 +58,94 @@ pub struct Project {
     pub manifest: Manifest,
     pub mods: Vec<Mod>,
     pub config_dir: Option<PathBuf>,
+    pub server_config_dir: Option<PathBuf>,
 }
 
 impl Mod {
-    pub fn new(name: String, file: Option<String>, source: Source) -> Self {
-        Mod { name, file, source }
+    pub fn new(
+        name: String,
+        file: Option<String>,
+        side: Option<Side>,
+        source: Source,
+        kind: ContentKind,
+    ) -> Self {
+        Mod {
+            name,
+            file,
+            side,
+            optional: None,
+            source,
+            kind,
+        }
+    }
+
+    /// Sets the side of the mod from the support declared on Modrinth.
+    pub fn with_support(self, client: SideSupport, server: SideSupport) -> Self {
+        Mod {
+            side: Some(Side::from_support(client, server)),
+            optional: Side::optional_from_support(client, server),
+            ..self
+        }
     }
 
-    pub fn build_source(&self, manifest: &Manifest) -> Result<BuildSource> {
-        BuildSource::generate(manifest, self)
+    /// Returns the support for the client and the server, if the mod has a side.
+    pub fn support(&self) -> Option<(SideSupport, SideSupport)> {
+        if self.side.is_none() && self.optional.is_none() {
+            return None;
+        }
+
+        let (client, server) = self.side.unwrap_or(Side::Both).support();
+        let optional = |side, support| match (self.optional, support) {
+            (Some(optional), SideSupport::Required)
+                if optional == Side::Both || optional == side =>
+            {
+                SideSupport::Optional
+            }
+            _ => support,
+        };
+        Some((
+            optional(Side::Client, client),
+            optional(Side::Server, server),
+        ))
+    }
+
+    /// Whether the mod is needed on the given side.
+    pub fn on_side(&self, side: Side) -> bool {
+        match self.side {
+            None | Some(Side::Both) => true,
+            Some(mod_side) => side == Side::Both || mod_side == side,
+        }
     }
 }
 
 impl Project {
-    pub fn new(manifest: Manifest, mods: Vec<Mod>, config_dir: Option<PathBuf>) -> Self {
+    pub fn new(
+        manifest: Manifest,
+        mods: Vec<Mod>,
+        config_dir: Option<PathBuf>,
+        server_config_dir: Option<PathBuf>,
+    ) -> Self {
         Self {
             manifest,
             mods,
             config_dir,
+            server_config_dir,
         }
     }
 
+    /// Filters the build sources to the ones needed on the given side.
+    pub fn side_sources(&self, sources: Vec<BuildSource>, side: Side) -> Vec<BuildSource> {
+        sources
+            .into_iter()
+            .filter(|source| {
+                self.mods
+                    .iter()
+                    .find(|mod_data| mod_data.name == source.name && mod_data.kind == source.kind)
+                    .is_none_or(|mod_data| mod_data.on_side(side))
+            })
+            .collect()
+    }
+
     pub fn read<P: AsRef<Path>>(path: P) -> Result<Self> {
         crate::toml::read_project(path)
     }
@@ -43,13 +153,103 @@ impl Project {
     pub fn write<P: AsRef<Path>>(&self, path: P) -> Result<()> {
         crate::toml::write_project(path, self.clone())
     }
+}
+
+impl Side {
+    /// Determines the side from the support declared on Modrinth.
+    pub fn from_support(client: SideSupport, server: SideSupport) -> Self {
+        match (client, server) {
+            // Nothing to go by, so keep the mod on both sides rather than dropping it
+            (SideSupport::Unsupported, SideSupport::Unsupported) => Side::Both,
+            (_, SideSupport::Unsupported) => Side::Client,
+            (SideSupport::Unsupported, _) => Side::Server,
+            _ => Side::Both,
+        }
+    }
+
+    /// Determines the sides on which the support declared on Modrinth is optional.
+    pub fn optional_from_support(client: SideSupport, server: SideSupport) -> Option<Self> {
+        match (client, server) {
+            (SideSupport::Optional, SideSupport::Optional) => Some(Side::Both),
+            (SideSupport::Optional, _) => Some(Side::Client),
+            (_, SideSupport::Optional) => Some(Side::Server),
+            _ => None,
+        }
+    }
 
-    pub fn build_sources(&self) -> Result<Vec<BuildSource>> {
-        let mut result = Vec::new();
-        for mod_data in &self.mods {
-            result.push(mod_data.build_source(&self.manifest)?);
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Side::Client => "client",
+            Side::Server => "server",
+            Side::Both => "both",
+        }
+    }
+
+    /// Returns the support for the client and the server.
+    pub fn support(&self) -> (SideSupport, SideSupport) {
+        match self {
+            Side::Client => (SideSupport::Required, SideSupport::Unsupported),
+            Side::Server => (SideSupport::Unsupported, SideSupport::Required),
+            Side::Both => (SideSupport::Required, SideSupport::Required),
+        }
+    }
+}
+
+impl ContentKind {
+    pub const ALL: [ContentKind; 5] = [
+        ContentKind::Mod,
+        ContentKind::ResourcePack,
+        ContentKind::ShaderPack,
+        ContentKind::Datapack,
+        ContentKind::Plugin,
+    ];
+
+    /// Name of the directory holding this kind of content, in both modpacks and instances.
+    ///
+    /// The datapacks directory of instances can be configured in the manifest.
+    pub fn dir_name(&self) -> &'static str {
+        match self {
+            ContentKind::Mod => "mods",
+            ContentKind::ResourcePack => "resourcepacks",
+            ContentKind::ShaderPack => "shaderpacks",
+            ContentKind::Datapack => "datapacks",
+            ContentKind::Plugin => "plugins",
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ContentKind::Mod => "mod",
+            ContentKind::ResourcePack => "resource pack",
+            ContentKind::ShaderPack => "shader pack",
+            ContentKind::Datapack => "datapack",
+            ContentKind::Plugin => "plugin",
+        }
+    }
+
+    pub fn from_project_type(project_type: &ProjectType) -> Option<Self> {
+        match project_type {
+            ProjectType::Mod => Some(ContentKind::Mod),
+            ProjectType::ResourcePack => Some(ContentKind::ResourcePack),
+            ProjectType::Shader => Some(ContentKind::ShaderPack),
+            ProjectType::Datapack => Some(ContentKind::Datapack),
+            ProjectType::Plugin => Some(ContentKind::Plugin),
+            ProjectType::Modpack | ProjectType::Unknown(_) => None,
+        }
+    }
+
+    /// Returns the loaders versions should be filtered by, any of them being compatible.
+    ///
+    /// Only mods depend on the modpack loader, packs have loaders of their own. Plugins
+    /// depend on it when it is a server that can run them.
+    pub fn loaders<'a>(&self, manifest: &'a Manifest) -> Option<Vec<&'a str>> {
+        match self {
+            ContentKind::Mod => manifest.loader.as_deref().map(|loader| vec![loader]),
+            ContentKind::Plugin if manifest.supports_plugins() => {
+                Some(manifest.plugin_loaders().to_vec())
+            }
+            _ => None,
         }
-        Ok(result)
     }
 }
 
@@ -59,6 +259,71 @@ impl From<Manifest> for Project {
             manifest: value,
             mods: vec![],
             config_dir: None,
+            server_config_dir: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use SideSupport::{Optional, Required, Unknown, Unsupported};
+
+    #[test]
+    fn side_from_support() {
+        assert_eq!(Side::from_support(Required, Unsupported), Side::Client);
+        assert_eq!(Side::from_support(Optional, Unsupported), Side::Client);
+        assert_eq!(Side::from_support(Unsupported, Required), Side::Server);
+        assert_eq!(Side::from_support(Unsupported, Optional), Side::Server);
+        assert_eq!(Side::from_support(Required, Required), Side::Both);
+        assert_eq!(Side::from_support(Optional, Required), Side::Both);
+        assert_eq!(Side::from_support(Unknown, Unknown), Side::Both);
+        assert_eq!(Side::from_support(Unsupported, Unsupported), Side::Both);
+    }
+
+    #[test]
+    fn optional_from_support() {
+        assert_eq!(Side::optional_from_support(Required, Required), None);
+        assert_eq!(
+            Side::optional_from_support(Optional, Required),
+            Some(Side::Client)
+        );
+        assert_eq!(
+            Side::optional_from_support(Required, Optional),
+            Some(Side::Server)
+        );
+        assert_eq!(
+            Side::optional_from_support(Optional, Optional),
+            Some(Side::Both)
+        );
+    }
+
+    #[test]
+    fn mod_keeps_optional_support() {
+        let mod_data = Mod::new(
+            "mod".to_string(),
+            None,
+            None,
+            Source::Modrinth {
+                version: "1.0.0".to_string(),
+            },
+            ContentKind::Mod,
+        );
+        assert_eq!(mod_data.support(), None);
+
+        for support in [
+            (Optional, Required),
+            (Required, Unsupported),
+            (Optional, Unsupported),
+            (Optional, Optional),
+        ] {
+            assert_eq!(
+                mod_data
+                    .clone()
+                    .with_support(support.0, support.1)
+                    .support(),
+                Some(support)
+            );
         }
     }
 }
@@ -1,60 +1,541 @@
-use crate::util::modrinth;
-use crate::{Manifest, Mod};
-use eyre::{ContextCompat, Result, WrapErr};
+use crate::util::github::{self, GitHubClient};
+use crate::util::modrinth::{self, ModrinthClient};
+use crate::{ContentKind, Manifest, Mod};
+use eyre::{bail, ensure, ContextCompat, Result, WrapErr};
+use glob::Pattern;
+use log::info;
 use serde::{Deserialize, Serialize};
+use sha1::Sha1;
+use sha2::{Digest, Sha512};
+use std::collections::HashMap;
+use std::path::{Component, Path};
 use url::Url;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(untagged, rename_all = "kebab-case")]
 pub enum Source {
     #[serde(rename_all = "kebab-case")]
-    Download { url: String },
+    Download {
+        url: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        sha512: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        sha1: Option<String>,
+    },
     #[serde(rename_all = "kebab-case")]
     Modrinth { version: String },
+    /// A file inside the project, relative to the project root.
+    #[serde(rename_all = "kebab-case")]
+    Path { path: String },
+    /// An asset of a GitHub release, matched by a glob pattern.
+    #[serde(rename_all = "kebab-case")]
+    GitHub {
+        repo: String,
+        tag: String,
+        asset: String,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum SourceKind {
+    /// Downloaded from a url
+    Download,
+
+    /// Hosted on Modrinth
+    Modrinth,
+
+    /// A file inside the modpack
+    Path,
+
+    /// An asset of a GitHub release
+    #[serde(rename = "github")]
+    #[value(name = "github")]
+    GitHub,
+}
+
+impl SourceKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SourceKind::Download => "download",
+            SourceKind::Modrinth => "modrinth",
+            SourceKind::Path => "path",
+            SourceKind::GitHub => "github",
+        }
+    }
+}
+
+impl Source {
+    pub fn kind(&self) -> SourceKind {
+        match self {
+            Source::Download { .. } => SourceKind::Download,
+            Source::Modrinth { .. } => SourceKind::Modrinth,
+            Source::Path { .. } => SourceKind::Path,
+            Source::GitHub { .. } => SourceKind::GitHub,
+        }
+    }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct BuildSource {
     pub name: String,
-    pub url: String,
+    pub project_id: Option<String>,
+    pub version_id: Option<String>,
+    pub version_number: Option<String>,
+    pub url: Option<String>,
+    /// Path of a local file, relative to the project root.
+    pub path: Option<String>,
     pub file: String,
     pub sha512: Option<String>,
+    pub sha1: Option<String>,
+    pub size: Option<u64>,
+    pub kind: ContentKind,
+    pub dependencies: Vec<modrinth::Dependency>,
 }
 
 impl BuildSource {
-    pub fn generate(manifest: &Manifest, mod_data: &Mod) -> Result<BuildSource> {
-        Ok(match &mod_data.source {
-            Source::Download { url } => BuildSource {
-                name: mod_data.name.to_string(),
-                file: Url::parse(url)?
-                    .path_segments()
-                    .and_then(|segments| segments.last())
-                    .and_then(|name| if name.is_empty() { None } else { Some(name) })
-                    .map(|s| s.into())
-                    .wrap_err("invalid url")?,
-                url: url.to_string(),
-                sha512: None,
-            },
-            Source::Modrinth { version } => {
-                let version = match modrinth::version(version) {
-                    Ok(version) => version,
-                    Err(_) => modrinth::project_versions(
-                        &mod_data.name,
-                        manifest.loader.as_deref(),
-                        manifest.minecraft_version.as_deref(),
-                    )
-                    .wrap_err("failed to fetch modrinth project versions")?
-                    .into_iter()
-                    .find(|modrinth_version| &modrinth_version.version_number == version)
-                    .wrap_err(format!("could not find version `{}`", version))?,
-                };
-                let file = version.primary_file().wrap_err("primary file not found")?;
-                BuildSource {
-                    name: mod_data.name.to_string(),
-                    url: file.url.to_string(),
-                    file: file.filename.to_string(),
-                    sha512: Some(file.hashes.sha512.to_string()),
+    /// Checks that the file name and the sha512 of the source can be used in paths, as
+    /// they may come from mod files, the lock file or an imported mrpack.
+    pub fn check(&self) -> Result<()> {
+        ensure!(
+            !self.file.contains(['/', '\\'])
+                && matches!(
+                    Path::new(&self.file).components().collect::<Vec<_>>()[..],
+                    [Component::Normal(_)]
+                ),
+            "invalid file name `{}` for mod `{}`",
+            self.file,
+            self.name
+        );
+
+        if let Some(sha512) = &self.sha512 {
+            ensure!(
+                sha512.len() == 128
+                    && sha512
+                        .chars()
+                        .all(|char| matches!(char, '0'..='9' | 'a'..='f')),
+                "invalid sha512 `{}` for mod `{}`",
+                sha512,
+                self.name
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Generates the build sources of multiple mods, fetching the Modrinth versions
+    /// referenced by id using a bulk request.
+    ///
+    /// Versions referenced by number are found among the versions of their projects
+    /// compatible with the manifest, using one request per project.
+    pub fn generate_all(
+        client: &ModrinthClient,
+        manifest: &Manifest,
+        mods: &[&Mod],
+    ) -> Result<Vec<BuildSource>> {
+        let ids: Vec<&str> = mods
+            .iter()
+            .filter_map(|mod_data| match &mod_data.source {
+                Source::Modrinth { version } if modrinth::check_id(version) => {
+                    Some(version.as_str())
                 }
-            }
+                _ => None,
+            })
+            .collect();
+
+        let versions: HashMap<String, modrinth::Version> = if ids.is_empty() {
+            HashMap::new()
+        } else {
+            client
+                .versions(&ids)
+                .wrap_err("failed to fetch modrinth versions")?
+                .into_iter()
+                .map(|version| (version.id.clone(), version))
+                .collect()
+        };
+
+        // Only create a GitHub client when a mod needs one
+        let github = mods
+            .iter()
+            .any(|mod_data| mod_data.source.kind() == SourceKind::GitHub)
+            .then(GitHubClient::from_env)
+            .transpose()
+            .wrap_err("failed to create github client")?;
+
+        mods.iter()
+            .map(|mod_data| {
+                match &mod_data.source {
+                    Source::Download { url, sha512, sha1 } => {
+                        BuildSource::from_download(mod_data, url, sha512, sha1)
+                    }
+                    Source::Modrinth { version } => match versions.get(version) {
+                        Some(version) => BuildSource::from_modrinth(mod_data, version),
+                        None => BuildSource::from_modrinth(
+                            mod_data,
+                            &modrinth_version_number(
+                                client,
+                                manifest,
+                                mod_data.kind,
+                                &mod_data.name,
+                                version,
+                            )?,
+                        ),
+                    },
+                    Source::Path { path } => BuildSource::from_path(mod_data, path),
+                    Source::GitHub { repo, tag, asset } => BuildSource::from_github(
+                        github
+                            .as_ref()
+                            .expect("github client should exist for github mods"),
+                        mod_data,
+                        repo,
+                        tag,
+                        asset,
+                    ),
+                }
+                .wrap_err(format!("failed to resolve mod `{}`", mod_data.name))
+            })
+            .collect()
+    }
+
+    fn from_download(
+        mod_data: &Mod,
+        url: &str,
+        sha512: &Option<String>,
+        sha1: &Option<String>,
+    ) -> Result<BuildSource> {
+        Ok(BuildSource {
+            name: mod_data.name.to_string(),
+            file: Url::parse(url)?
+                .path_segments()
+                .and_then(|mut segments| segments.next_back())
+                .and_then(|name| if name.is_empty() { None } else { Some(name) })
+                .map(|s| s.into())
+                .wrap_err("invalid url")?,
+            url: Some(url.to_string()),
+            path: None,
+            project_id: None,
+            version_id: None,
+            version_number: None,
+            sha512: sha512.clone(),
+            sha1: sha1.clone(),
+            size: None,
+            kind: mod_data.kind,
+            dependencies: Vec::new(),
+        })
+    }
+
+    fn from_modrinth(mod_data: &Mod, version: &modrinth::Version) -> Result<BuildSource> {
+        let file = version.primary_file().wrap_err("primary file not found")?;
+        Ok(BuildSource {
+            name: mod_data.name.to_string(),
+            url: Some(file.url.to_string()),
+            path: None,
+            file: file.filename.to_string(),
+            project_id: Some(version.project_id.to_string()),
+            version_id: Some(version.id.to_string()),
+            version_number: Some(version.version_number.to_string()),
+            sha512: Some(file.hashes.sha512.to_string()),
+            sha1: Some(file.hashes.sha1.to_string()),
+            size: Some(file.size),
+            kind: mod_data.kind,
+            dependencies: version.dependencies.clone(),
+        })
+    }
+
+    fn from_github(
+        client: &GitHubClient,
+        mod_data: &Mod,
+        repo: &str,
+        tag: &str,
+        asset: &str,
+    ) -> Result<BuildSource> {
+        let release = client
+            .release(repo, tag)
+            .wrap_err("failed to fetch github release")?;
+        let asset = release_asset(&release, asset)?;
+
+        // GitHub doesn't provide the hashes needed to verify the asset, so it is hashed
+        // once here and the hashes are kept in the lock file
+        info!("Hashing {}", &asset.name);
+        let body = client
+            .download(asset)
+            .wrap_err("failed to download github release asset")?;
+
+        Ok(BuildSource {
+            name: mod_data.name.to_string(),
+            url: Some(asset.browser_download_url.to_string()),
+            path: None,
+            file: asset.name.to_string(),
+            project_id: None,
+            version_id: None,
+            version_number: None,
+            sha512: Some(hex::encode(Sha512::digest(&body))),
+            sha1: Some(hex::encode(Sha1::digest(&body))),
+            size: Some(body.len() as u64),
+            kind: mod_data.kind,
+            dependencies: Vec::new(),
         })
     }
+
+    fn from_path(mod_data: &Mod, path: &str) -> Result<BuildSource> {
+        ensure!(
+            Path::new(path)
+                .components()
+                .all(|component| matches!(component, Component::Normal(_) | Component::CurDir)),
+            "path `{}` must be relative to the modpack and stay inside it",
+            path
+        );
+
+        Ok(BuildSource {
+            name: mod_data.name.to_string(),
+            file: Path::new(path)
+                .file_name()
+                .and_then(|name| name.to_str())
+                .map(|name| name.into())
+                .wrap_err("invalid path")?,
+            url: None,
+            path: Some(path.to_string()),
+            project_id: None,
+            version_id: None,
+            version_number: None,
+            // Local files can change at any time, so they are hashed when building
+            sha512: None,
+            sha1: None,
+            size: None,
+            kind: mod_data.kind,
+            dependencies: Vec::new(),
+        })
+    }
+}
+
+/// Finds the only asset of a GitHub release matching a glob pattern.
+pub fn release_asset<'a>(release: &'a github::Release, pattern: &str) -> Result<&'a github::Asset> {
+    let glob = Pattern::new(pattern).wrap_err(format!("invalid asset pattern `{}`", pattern))?;
+    let assets: Vec<&github::Asset> = release
+        .assets
+        .iter()
+        .filter(|asset| glob.matches(&asset.name))
+        .collect();
+
+    match assets[..] {
+        [asset] => Ok(asset),
+        [] => bail!(
+            "no asset of release `{}` matches `{}`",
+            release.tag_name,
+            pattern
+        ),
+        _ => bail!(
+            "multiple assets of release `{}` match `{}`: {}",
+            release.tag_name,
+            pattern,
+            assets
+                .iter()
+                .map(|asset| format!("`{}`", asset.name))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    }
+}
+
+/// Fetches a version of a Modrinth project, referenced by an id or a version number.
+pub fn modrinth_version(
+    client: &ModrinthClient,
+    manifest: &Manifest,
+    kind: ContentKind,
+    project: &str,
+    version: &str,
+) -> Result<modrinth::Version> {
+    match client.version(version) {
+        Ok(version) => Ok(version),
+        Err(_) => modrinth_version_number(client, manifest, kind, project, version),
+    }
+}
+
+/// Fetches a version of a Modrinth project compatible with the manifest by its number.
+fn modrinth_version_number(
+    client: &ModrinthClient,
+    manifest: &Manifest,
+    kind: ContentKind,
+    project: &str,
+    version: &str,
+) -> Result<modrinth::Version> {
+    let versions = client
+        .project_versions(
+            project,
+            kind.loaders(manifest).as_deref(),
+            manifest.minecraft_version.as_deref(),
+        )
+        .wrap_err("failed to fetch modrinth project versions")?;
+
+    find_version_number(&versions, manifest, kind, version).cloned()
+}
+
+/// Finds the newest version of a project compatible with the manifest by its number.
+fn find_version_number<'a>(
+    versions: &'a [modrinth::Version],
+    manifest: &Manifest,
+    kind: ContentKind,
+    version: &str,
+) -> Result<&'a modrinth::Version> {
+    let loaders = kind.loaders(manifest);
+    let game_version = manifest.minecraft_version.as_deref();
+
+    versions
+        .iter()
+        .filter(|modrinth_version| modrinth_version.version_number == version)
+        .filter(|modrinth_version| {
+            loaders.as_ref().is_none_or(|loaders| {
+                modrinth_version
+                    .loaders
+                    .iter()
+                    .any(|loader| loaders.contains(&loader.as_str()))
+            })
+        })
+        .filter(|modrinth_version| {
+            game_version.is_none_or(|game_version| {
+                modrinth_version
+                    .game_versions
+                    .iter()
+                    .any(|v| v == game_version)
+            })
+        })
+        .max_by_key(|modrinth_version| modrinth_version.date_published)
+        .wrap_err(format!("could not find version `{}`", version))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn source(string: &str) -> Source {
+        toml::from_str(string).unwrap()
+    }
+
+    fn release(assets: &[&str]) -> github::Release {
+        github::Release {
+            tag_name: "v1.0".to_string(),
+            assets: assets
+                .iter()
+                .map(|name| github::Asset {
+                    name: name.to_string(),
+                    browser_download_url: format!("https://github.com/me/mod/{}", name),
+                    size: 1,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn deserializes_download() {
+        assert_eq!(
+            source(r#"url = "https://example.com/mod.jar""#),
+            Source::Download {
+                url: "https://example.com/mod.jar".to_string(),
+                sha512: None,
+                sha1: None,
+            }
+        );
+        assert_eq!(
+            source("url = \"https://example.com/mod.jar\"\nsha1 = \"abc\""),
+            Source::Download {
+                url: "https://example.com/mod.jar".to_string(),
+                sha512: None,
+                sha1: Some("abc".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn deserializes_modrinth() {
+        assert_eq!(
+            source(r#"version = "1.0.0""#),
+            Source::Modrinth {
+                version: "1.0.0".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn deserializes_path() {
+        assert_eq!(
+            source(r#"path = "files/mod.jar""#),
+            Source::Path {
+                path: "files/mod.jar".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn deserializes_github() {
+        assert_eq!(
+            source("repo = \"me/mod\"\ntag = \"v1.0\"\nasset = \"*.jar\""),
+            Source::GitHub {
+                repo: "me/mod".to_string(),
+                tag: "v1.0".to_string(),
+                asset: "*.jar".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_sources() {
+        assert!(toml::from_str::<Source>(r#"unknown = "value""#).is_err());
+    }
+
+    #[test]
+    fn matches_single_asset() {
+        let release = release(&["mod-1.0.jar", "mod-1.0-sources.jar", "README.md"]);
+
+        assert_eq!(
+            release_asset(&release, "mod-*[0-9].jar").unwrap().name,
+            "mod-1.0.jar"
+        );
+        assert_eq!(release_asset(&release, "*.md").unwrap().name, "README.md");
+    }
+
+    #[test]
+    fn rejects_no_or_multiple_assets() {
+        let release = release(&["mod-1.0.jar", "mod-1.0-sources.jar"]);
+
+        assert!(release_asset(&release, "*.zip").is_err());
+        assert!(release_asset(&release, "*.jar").is_err());
+        assert!(release_asset(&release, "[").is_err());
+    }
+
+    fn version(id: &str, number: &str, loader: &str, day: u32) -> modrinth::Version {
+        modrinth::Version {
+            id: id.to_string(),
+            project_id: "AABBCCDD".to_string(),
+            name: number.to_string(),
+            version_number: number.to_string(),
+            date_published: Utc.with_ymd_and_hms(2023, 6, day, 0, 0, 0).unwrap(),
+            game_versions: vec!["1.20.1".to_string()],
+            loaders: vec![loader.to_string()],
+            files: Vec::new(),
+            dependencies: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn finds_newest_compatible_version_number() {
+        let manifest = Manifest::new(
+            "pack".to_string(),
+            "1.0.0".to_string(),
+            Some("1.20.1".to_string()),
+            Some("fabric".to_string()),
+            None,
+        );
+        let versions = [
+            version("FORGEFOR", "1.0.0", "forge", 3),
+            version("FABRICNE", "1.0.0", "fabric", 2),
+            version("FABRICOL", "1.0.0", "fabric", 1),
+            version("FABRICOT", "2.0.0", "fabric", 4),
+        ];
+
+        let found = find_version_number(&versions, &manifest, ContentKind::Mod, "1.0.0").unwrap();
+
+        assert_eq!(found.id, "FABRICNE");
+        assert!(find_version_number(&versions, &manifest, ContentKind::Mod, "3.0.0").is_err());
+    }
 }
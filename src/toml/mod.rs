@@ -1,6 +1,12 @@
-use crate::{Manifest, Mod, Project, Source};
-use eyre::{Result, WrapErr};
+use crate::source::BuildSource;
+use crate::util::modrinth;
+use crate::{
+    BuildManifest, ContentKind, LockedMod, Lockfile, Manifest, Mod, Project, ServerManifest, Side,
+    Source,
+};
+use eyre::{ensure, Result, WrapErr};
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
 use std::ffi::OsStr;
 use std::fs;
 use std::path::{Path, PathBuf};
@@ -8,8 +14,21 @@ use std::path::{Path, PathBuf};
 pub fn read_project<P: AsRef<Path>>(path: P) -> Result<Project> {
     let manifest = read_manifest(path.as_ref().join_manifest_file())
         .wrap_err("failed to read manifest file")?;
-    let mods =
+    let mut mods =
         read_mods(path.as_ref().join_mods_dir()).wrap_err("failed to read mods directory")?;
+    for kind in ContentKind::ALL {
+        let dir = path.as_ref().join_content_dir(kind);
+        if kind == ContentKind::Mod || !dir.is_dir() {
+            continue;
+        }
+
+        mods.extend(
+            read_mods(dir)
+                .wrap_err(format!("failed to read {} directory", kind.dir_name()))?
+                .into_iter()
+                .map(|mod_data| Mod { kind, ..mod_data }),
+        );
+    }
     let config_dir = Some(path.as_ref().join_config_dir()).and_then(|path| {
         if path.exists() && path.is_dir() {
             Some(path)
@@ -17,8 +36,15 @@ pub fn read_project<P: AsRef<Path>>(path: P) -> Result<Project> {
             None
         }
     });
+    let server_config_dir = Some(path.as_ref().join_server_config_dir()).and_then(|path| {
+        if path.exists() && path.is_dir() {
+            Some(path)
+        } else {
+            None
+        }
+    });
 
-    Ok(Project::new(manifest, mods, config_dir))
+    Ok(Project::new(manifest, mods, config_dir, server_config_dir))
 }
 
 pub fn read_manifest<P: AsRef<Path>>(path: P) -> Result<Manifest> {
@@ -27,8 +53,11 @@ pub fn read_manifest<P: AsRef<Path>>(path: P) -> Result<Manifest> {
 }
 
 pub fn read_manifest_from_str(string: &str) -> Result<Manifest> {
-    let manifest = toml::from_str::<TomlManifest>(string)?;
-    Ok(manifest.into())
+    let manifest: Manifest = toml::from_str::<TomlManifest>(string)?.into();
+    if let Some(build) = &manifest.build {
+        build.check().wrap_err("invalid build section")?;
+    }
+    Ok(manifest)
 }
 
 pub fn read_mods<P: AsRef<Path>>(path: P) -> Result<Vec<Mod>> {
@@ -58,8 +87,20 @@ pub fn read_mod_from_str(string: &str) -> Result<Mod> {
 pub fn write_project<P: AsRef<Path>>(path: P, project: Project) -> Result<()> {
     write_manifest(path.as_ref().join_manifest_file(), project.manifest)
         .wrap_err("failed to write manifest file")?;
-    write_mods(path.as_ref().join_mods_dir(), project.mods)
-        .wrap_err("failed to write to mods directory")?;
+    for kind in ContentKind::ALL {
+        let mods: Vec<Mod> = project
+            .mods
+            .iter()
+            .filter(|mod_data| mod_data.kind == kind)
+            .cloned()
+            .collect();
+        if kind != ContentKind::Mod && mods.is_empty() {
+            continue;
+        }
+
+        write_mods(path.as_ref().join_content_dir(kind), mods)
+            .wrap_err(format!("failed to write to {} directory", kind.dir_name()))?;
+    }
 
     Ok(())
 }
@@ -74,6 +115,16 @@ pub fn write_mods<P: AsRef<Path>>(path: P, mods: Vec<Mod>) -> Result<()> {
     if !path.as_ref().exists() {
         fs::create_dir(&path)?;
     }
+
+    let mut names = HashSet::new();
+    for mod_data in &mods {
+        ensure!(
+            names.insert(&mod_data.name),
+            "multiple mods are named `{}`",
+            mod_data.name
+        );
+    }
+
     for mod_data in mods {
         write_mod(path.as_ref().join_mod_file(&mod_data.name), mod_data)
             .wrap_err("failed to write mod file")?;
@@ -87,10 +138,31 @@ pub fn write_mod<P: AsRef<Path>>(path: P, mod_data: Mod) -> Result<()> {
     Ok(())
 }
 
+const LOCKFILE_HEADER: &str = "# This file is automatically generated by niterpack.\n\
+                               # It is not intended for manual editing.\n";
+
+pub fn read_lockfile<P: AsRef<Path>>(path: P) -> Result<Lockfile> {
+    let string = fs::read_to_string(path)?;
+    read_lockfile_from_str(&string)
+}
+
+pub fn read_lockfile_from_str(string: &str) -> Result<Lockfile> {
+    let lockfile = toml::from_str::<TomlLockfile>(string)?;
+    Ok(lockfile.into())
+}
+
+pub fn write_lockfile<P: AsRef<Path>>(path: P, lockfile: Lockfile) -> Result<()> {
+    let string = toml::to_string(&TomlLockfile::from(lockfile))?;
+    fs::write(path, format!("{}{}", LOCKFILE_HEADER, string))?;
+    Ok(())
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct TomlManifest {
     pub modpack: TomlManifestModpack,
     pub minecraft: Option<TomlManifestMinecraft>,
+    pub server: Option<TomlManifestServer>,
+    pub build: Option<TomlManifestBuild>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -100,8 +172,10 @@ pub struct TomlManifestModpack {
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
 pub struct TomlManifestMinecraft {
     pub loader: Option<String>,
+    pub loader_version: Option<String>,
     pub version: Option<String>,
 }
 
@@ -109,21 +183,86 @@ pub struct TomlManifestMinecraft {
 pub struct TomlMod {
     pub name: String,
     pub file: Option<String>,
+    pub side: Option<Side>,
+    pub optional: Option<Side>,
     #[serde(flatten)]
     pub source: Source,
 }
 
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct TomlManifestServer {
+    #[serde(default)]
+    pub eula: bool,
+    #[serde(default)]
+    pub start_scripts: bool,
+    pub jar: Option<String>,
+    pub memory: Option<String>,
+    #[serde(default)]
+    pub properties: BTreeMap<String, toml::Value>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct TomlManifestBuild {
+    pub jobs: Option<usize>,
+    pub datapacks_dir: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct TomlLockfile {
+    pub minecraft_version: Option<String>,
+    pub loader: Option<String>,
+    #[serde(default, rename = "mod")]
+    pub mods: Vec<TomlLockedMod>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct TomlLockedMod {
+    pub name: String,
+    pub project_id: Option<String>,
+    pub version_id: Option<String>,
+    pub version_number: Option<String>,
+    pub url: Option<String>,
+    pub path: Option<String>,
+    pub file: String,
+    pub size: Option<u64>,
+    pub sha512: Option<String>,
+    pub sha1: Option<String>,
+    pub source: Source,
+    #[serde(default)]
+    pub kind: ContentKind,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dependencies: Vec<modrinth::Dependency>,
+}
+
 impl From<TomlManifest> for Manifest {
     fn from(value: TomlManifest) -> Self {
-        Manifest::new(
+        let server = value.server.map(ServerManifest::from);
+        let build = value.build.map(BuildManifest::from);
+        let manifest = Manifest::new(
             value.modpack.name,
             value.modpack.version,
             value
                 .minecraft
                 .clone()
                 .and_then(|minecraft| minecraft.version),
-            value.minecraft.and_then(|minecraft| minecraft.loader),
-        )
+            value
+                .minecraft
+                .clone()
+                .and_then(|minecraft| minecraft.loader),
+            value
+                .minecraft
+                .and_then(|minecraft| minecraft.loader_version),
+        );
+
+        Manifest {
+            server,
+            build,
+            ..manifest
+        }
     }
 }
 
@@ -134,7 +273,73 @@ impl From<Manifest> for TomlManifest {
                 name: value.name,
                 version: value.version,
             },
-            minecraft: None,
+            minecraft: if value.minecraft_version.is_some()
+                || value.loader.is_some()
+                || value.loader_version.is_some()
+            {
+                Some(TomlManifestMinecraft {
+                    loader: value.loader,
+                    loader_version: value.loader_version,
+                    version: value.minecraft_version,
+                })
+            } else {
+                None
+            },
+            server: value.server.map(TomlManifestServer::from),
+            build: value.build.map(TomlManifestBuild::from),
+        }
+    }
+}
+
+impl From<TomlManifestBuild> for BuildManifest {
+    fn from(value: TomlManifestBuild) -> Self {
+        BuildManifest {
+            jobs: value.jobs,
+            datapacks_dir: value.datapacks_dir,
+        }
+    }
+}
+
+impl From<BuildManifest> for TomlManifestBuild {
+    fn from(value: BuildManifest) -> Self {
+        TomlManifestBuild {
+            jobs: value.jobs,
+            datapacks_dir: value.datapacks_dir,
+        }
+    }
+}
+
+impl From<TomlManifestServer> for ServerManifest {
+    fn from(value: TomlManifestServer) -> Self {
+        ServerManifest {
+            eula: value.eula,
+            start_scripts: value.start_scripts,
+            jar: value.jar,
+            memory: value.memory,
+            properties: value
+                .properties
+                .into_iter()
+                .map(|(key, value)| match value {
+                    toml::Value::String(value) => (key, value),
+                    value => (key, value.to_string()),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<ServerManifest> for TomlManifestServer {
+    fn from(value: ServerManifest) -> Self {
+        TomlManifestServer {
+            eula: value.eula,
+            start_scripts: value.start_scripts,
+            jar: value.jar,
+            memory: value.memory,
+            properties: value
+                .properties
+                .into_iter()
+                .map(|(key, value)| (key, toml::Value::String(value)))
+                .collect(),
         }
     }
 }
@@ -147,7 +352,16 @@ impl From<TomlManifest> for Project {
 
 impl From<TomlMod> for Mod {
     fn from(value: TomlMod) -> Self {
-        Mod::new(value.name, value.file, value.source)
+        Mod {
+            optional: value.optional,
+            ..Mod::new(
+                value.name,
+                value.file,
+                value.side,
+                value.source,
+                ContentKind::default(),
+            )
+        }
     }
 }
 
@@ -156,16 +370,84 @@ impl From<Mod> for TomlMod {
         TomlMod {
             name: value.name,
             file: value.file,
+            side: value.side,
+            optional: value.optional,
+            source: value.source,
+        }
+    }
+}
+
+impl From<TomlLockfile> for Lockfile {
+    fn from(value: TomlLockfile) -> Self {
+        Lockfile::new(
+            value.minecraft_version,
+            value.loader,
+            value.mods.into_iter().map(LockedMod::from).collect(),
+        )
+    }
+}
+
+impl From<Lockfile> for TomlLockfile {
+    fn from(value: Lockfile) -> Self {
+        TomlLockfile {
+            minecraft_version: value.minecraft_version,
+            loader: value.loader,
+            mods: value.mods.into_iter().map(TomlLockedMod::from).collect(),
+        }
+    }
+}
+
+impl From<TomlLockedMod> for LockedMod {
+    fn from(value: TomlLockedMod) -> Self {
+        LockedMod {
+            source: value.source,
+            build: BuildSource {
+                name: value.name,
+                project_id: value.project_id,
+                version_id: value.version_id,
+                version_number: value.version_number,
+                url: value.url,
+                path: value.path,
+                file: value.file,
+                sha512: value.sha512,
+                sha1: value.sha1,
+                size: value.size,
+                kind: value.kind,
+                dependencies: value.dependencies,
+            },
+        }
+    }
+}
+
+impl From<LockedMod> for TomlLockedMod {
+    fn from(value: LockedMod) -> Self {
+        TomlLockedMod {
+            name: value.build.name,
+            project_id: value.build.project_id,
+            version_id: value.build.version_id,
+            version_number: value.build.version_number,
+            url: value.build.url,
+            path: value.build.path,
+            file: value.build.file,
+            size: value.build.size,
+            sha512: value.build.sha512,
+            sha1: value.build.sha1,
             source: value.source,
+            kind: value.build.kind,
+            dependencies: value.build.dependencies,
         }
     }
 }
 
 pub trait JoinToml {
     fn join_manifest_file(&self) -> PathBuf;
+    fn join_lock_file(&self) -> PathBuf;
     fn join_mods_dir(&self) -> PathBuf;
+    fn join_content_dir(&self, kind: ContentKind) -> PathBuf;
     fn join_mod_file(&self, name: &str) -> PathBuf;
     fn join_config_dir(&self) -> PathBuf;
+    fn join_server_config_dir(&self) -> PathBuf;
+    fn join_files_dir(&self) -> PathBuf;
 }
 
 impl JoinToml for Path {
@@ -173,15 +455,124 @@ impl JoinToml for Path {
         self.join("niterpack").with_extension("toml")
     }
 
+    fn join_lock_file(&self) -> PathBuf {
+        self.join("niterpack").with_extension("lock")
+    }
+
     fn join_mods_dir(&self) -> PathBuf {
-        self.join("mods")
+        self.join_content_dir(ContentKind::Mod)
+    }
+
+    fn join_content_dir(&self, kind: ContentKind) -> PathBuf {
+        self.join(kind.dir_name())
     }
 
     fn join_mod_file(&self, name: &str) -> PathBuf {
-        self.join(name).with_extension("toml")
+        // Names can contain dots, like the file stems of local files
+        self.join(format!("{}.toml", name))
     }
 
     fn join_config_dir(&self) -> PathBuf {
         self.join("config")
     }
+
+    fn join_server_config_dir(&self) -> PathBuf {
+        self.join("server-config")
+    }
+
+    fn join_files_dir(&self) -> PathBuf {
+        self.join("files")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::modrinth::{Dependency, DependencyType};
+    use std::env;
+
+    fn locked_mod(name: &str, kind: ContentKind, source: Source) -> LockedMod {
+        LockedMod {
+            source,
+            build: BuildSource {
+                name: name.to_string(),
+                project_id: Some("AABBCCDD".to_string()),
+                version_id: Some("EEFFGGHH".to_string()),
+                version_number: Some("1.0.0".to_string()),
+                url: Some(format!("https://cdn.example.com/{}.jar", name)),
+                path: None,
+                file: format!("{}.jar", name),
+                sha512: Some("abc".to_string()),
+                sha1: Some("def".to_string()),
+                size: Some(42),
+                kind,
+                dependencies: vec![Dependency {
+                    version_id: None,
+                    project_id: Some("IIJJKKLL".to_string()),
+                    file_name: None,
+                    dependency_type: DependencyType::Required,
+                }],
+            },
+        }
+    }
+
+    #[test]
+    fn lockfile_round_trip() {
+        let lockfile = Lockfile::new(
+            Some("1.20.1".to_string()),
+            Some("fabric".to_string()),
+            vec![
+                locked_mod(
+                    "sodium",
+                    ContentKind::Mod,
+                    Source::Modrinth {
+                        version: "1.0.0".to_string(),
+                    },
+                ),
+                locked_mod(
+                    "pack",
+                    ContentKind::ResourcePack,
+                    Source::Modrinth {
+                        version: "EEFFGGHH".to_string(),
+                    },
+                ),
+            ],
+        );
+
+        let path = env::temp_dir().join(format!("niterpack-lock-{}.lock", std::process::id()));
+        write_lockfile(&path, lockfile.clone()).unwrap();
+        let string = fs::read_to_string(&path).unwrap();
+        let read = read_lockfile(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert!(string.starts_with(LOCKFILE_HEADER));
+        assert_eq!(read, lockfile);
+    }
+
+    #[test]
+    fn lockfile_defaults_to_mods() {
+        let lockfile = read_lockfile_from_str(
+            r#"
+            [[mod]]
+            name = "sodium"
+            file = "sodium.jar"
+            url = "https://cdn.example.com/sodium.jar"
+
+            [mod.source]
+            version = "1.0.0"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(lockfile.mods[0].build.kind, ContentKind::Mod);
+        assert!(lockfile.mods[0].build.dependencies.is_empty());
+    }
+
+    #[test]
+    fn keeps_dots_in_mod_file_names() {
+        assert_eq!(
+            Path::new("mods").join_mod_file("a-1.2.3"),
+            Path::new("mods").join("a-1.2.3.toml")
+        );
+    }
 }
@@ -0,0 +1,241 @@
+use eyre::{Result, WrapErr};
+use log::warn;
+use reqwest::blocking::Client;
+use reqwest::header::RANGE;
+use reqwest::StatusCode;
+use std::fs::{self, File, OpenOptions};
+use std::io;
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+const TIMEOUT: Duration = Duration::from_secs(300);
+const MAX_ATTEMPTS: u32 = 4;
+const BACKOFF: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DownloadReport {
+    pub attempts: u32,
+    pub resumed: bool,
+}
+
+/// Creates a client with the timeouts used for downloads.
+pub fn client() -> reqwest::Result<Client> {
+    Client::builder()
+        .connect_timeout(CONNECT_TIMEOUT)
+        .timeout(TIMEOUT)
+        .build()
+}
+
+/// Streams a url into a file, retrying transient failures with an exponential backoff.
+///
+/// If the file already exists, the download is resumed using a range request when the
+/// server supports it.
+pub fn download(client: &Client, path: &Path, url: &str) -> Result<DownloadReport> {
+    let mut report = DownloadReport::default();
+
+    loop {
+        report.attempts += 1;
+
+        let err = match attempt(client, path, url) {
+            Ok(resumed) => {
+                report.resumed |= resumed;
+                return Ok(report);
+            }
+            Err(err) => err,
+        };
+
+        if !err.transient || report.attempts >= MAX_ATTEMPTS {
+            return Err(err.report).wrap_err(format!(
+                "download failed after {} attempts",
+                report.attempts
+            ));
+        }
+
+        let backoff = BACKOFF * 2u32.pow(report.attempts - 1);
+        warn!(
+            "failed to download `{}`, retrying in {}s: {:#}",
+            url,
+            backoff.as_secs(),
+            err.report
+        );
+        thread::sleep(backoff);
+    }
+}
+
+struct AttemptError {
+    report: eyre::Report,
+    transient: bool,
+}
+
+impl AttemptError {
+    fn transient(report: impl Into<eyre::Report>) -> Self {
+        AttemptError {
+            report: report.into(),
+            transient: true,
+        }
+    }
+
+    fn fatal(report: impl Into<eyre::Report>) -> Self {
+        AttemptError {
+            report: report.into(),
+            transient: false,
+        }
+    }
+}
+
+/// Makes a single download attempt, returning whether the download was resumed.
+fn attempt(client: &Client, path: &Path, url: &str) -> Result<bool, AttemptError> {
+    let existing = fs::metadata(path)
+        .map(|metadata| metadata.len())
+        .unwrap_or(0);
+
+    let mut request = client.get(url);
+    if existing > 0 {
+        request = request.header(RANGE, format!("bytes={}-", existing));
+    }
+
+    let mut response = request.send().map_err(|err| {
+        if err.is_timeout() || err.is_connect() || err.is_request() {
+            AttemptError::transient(err)
+        } else {
+            AttemptError::fatal(err)
+        }
+    })?;
+
+    let status = response.status();
+    let (mut file, resumed) = match status {
+        StatusCode::PARTIAL_CONTENT => (
+            OpenOptions::new()
+                .append(true)
+                .open(path)
+                .map_err(AttemptError::fatal)?,
+            true,
+        ),
+        StatusCode::RANGE_NOT_SATISFIABLE => {
+            // The partial file is unusable, start over on the next attempt
+            fs::remove_file(path).map_err(AttemptError::fatal)?;
+            return Err(AttemptError::transient(eyre::eyre!(
+                "server could not resume the download"
+            )));
+        }
+        status if status.is_success() => (File::create(path).map_err(AttemptError::fatal)?, false),
+        status => {
+            let err = eyre::eyre!("server responded with status {}", status);
+            return Err(
+                if status.is_server_error()
+                    || status == StatusCode::TOO_MANY_REQUESTS
+                    || status == StatusCode::REQUEST_TIMEOUT
+                {
+                    AttemptError::transient(err)
+                } else {
+                    AttemptError::fatal(err)
+                },
+            );
+        }
+    };
+
+    io::copy(&mut response, &mut file).map_err(|err| {
+        AttemptError::transient(eyre::Report::new(err).wrap_err("failed to write response"))
+    })?;
+
+    Ok(resumed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::path::PathBuf;
+    use std::thread::JoinHandle;
+
+    /// Serves one response per connection, returning the received requests.
+    fn serve(responses: Vec<(&'static str, &'static str)>) -> (String, JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/mod.jar", listener.local_addr().unwrap());
+
+        let handle = thread::spawn(move || {
+            let mut requests = Vec::new();
+            for (status, body) in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+
+                let mut request = Vec::new();
+                let mut buf = [0; 1024];
+                while !request.ends_with(b"\r\n\r\n") {
+                    let read = stream.read(&mut buf).unwrap();
+                    request.extend_from_slice(&buf[..read]);
+                }
+                requests.push(String::from_utf8(request).unwrap().to_lowercase());
+
+                write!(
+                    stream,
+                    "HTTP/1.1 {}\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                )
+                .unwrap();
+            }
+            requests
+        });
+
+        (url, handle)
+    }
+
+    fn file(name: &str) -> PathBuf {
+        let path = env::temp_dir().join(format!(
+            "niterpack-download-{}-{}.jar",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn resumes_partial_downloads() {
+        let (url, server) = serve(vec![("206 Partial Content", "d")]);
+        let path = file("resume");
+        fs::write(&path, "mo").unwrap();
+
+        let report = download(&client().unwrap(), &path, &url).unwrap();
+        let requests = server.join().unwrap();
+        let contents = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(contents, "mod");
+        assert!(report.resumed);
+        assert_eq!(report.attempts, 1);
+        assert!(requests[0].contains("range: bytes=2-"));
+    }
+
+    #[test]
+    fn retries_server_errors() {
+        let (url, server) = serve(vec![("503 Service Unavailable", ""), ("200 OK", "mod")]);
+        let path = file("retry");
+
+        let report = download(&client().unwrap(), &path, &url).unwrap();
+        server.join().unwrap();
+        let contents = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(contents, "mod");
+        assert!(!report.resumed);
+        assert_eq!(report.attempts, 2);
+    }
+
+    #[test]
+    fn fails_on_client_errors() {
+        let (url, server) = serve(vec![("404 Not Found", "")]);
+        let path = file("missing");
+
+        let err = download(&client().unwrap(), &path, &url).unwrap_err();
+        server.join().unwrap();
+
+        assert_eq!(err.to_string(), "download failed after 1 attempts");
+        assert!(!path.exists());
+    }
+}
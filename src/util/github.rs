@@ -0,0 +1,142 @@
+use crate::util::modrinth::USER_AGENT;
+use reqwest::blocking::RequestBuilder;
+use reqwest::header::{ACCEPT, AUTHORIZATION};
+use reqwest::{StatusCode, Url};
+use serde::{Deserialize, Serialize};
+use std::env;
+
+pub const DEFAULT_BASE_URL: &str = "https://api.github.com";
+
+#[derive(thiserror::Error, Debug)]
+pub enum GitHubError {
+    #[error("reqwest error")]
+    Reqwest(#[from] reqwest::Error),
+
+    #[error("invalid github url `{0}`")]
+    InvalidUrl(String),
+
+    #[error("invalid repository `{0}`, expected `owner/name`")]
+    InvalidRepo(String),
+
+    #[error("github responded with status {status} for release `{release}`")]
+    Status { status: StatusCode, release: String },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Release {
+    pub tag_name: String,
+    pub assets: Vec<Asset>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Asset {
+    pub name: String,
+    pub browser_download_url: String,
+    pub size: u64,
+}
+
+/// A client for the releases of the GitHub REST API.
+#[derive(Debug, Clone)]
+pub struct GitHubClient {
+    client: reqwest::blocking::Client,
+    base_url: Url,
+    token: Option<String>,
+}
+
+pub fn check_repo(repo: &str) -> bool {
+    lazy_regex::regex_is_match!(r#"^[\w.\-]+/[\w.\-]+$"#, repo)
+}
+
+/// Splits a repository into its owner and name.
+fn split_repo(repo: &str) -> Result<(&str, &str), GitHubError> {
+    repo.split_once('/')
+        .filter(|_| check_repo(repo))
+        .ok_or_else(|| GitHubError::InvalidRepo(repo.to_string()))
+}
+
+impl GitHubClient {
+    pub fn new(base_url: String, token: Option<String>) -> Result<Self, GitHubError> {
+        let url = Url::parse(&base_url)
+            .ok()
+            .filter(|url| !url.cannot_be_a_base())
+            .ok_or(GitHubError::InvalidUrl(base_url))?;
+
+        Ok(Self {
+            client: reqwest::blocking::Client::builder()
+                .user_agent(USER_AGENT)
+                .build()?,
+            base_url: url,
+            token,
+        })
+    }
+
+    /// Creates a client configured by the `NITER_GITHUB_URL` and `GITHUB_TOKEN` environment
+    /// variables.
+    pub fn from_env() -> Result<Self, GitHubError> {
+        Self::new(
+            env::var("NITER_GITHUB_URL").unwrap_or_else(|_| DEFAULT_BASE_URL.to_string()),
+            env::var("GITHUB_TOKEN").ok(),
+        )
+    }
+
+    /// Creates a request to the API, with each part of `path` encoded as a single segment.
+    fn request(&self, path: &[&str]) -> RequestBuilder {
+        let mut url = self.base_url.clone();
+        url.path_segments_mut()
+            .expect("base url should be checked when creating the client")
+            .pop_if_empty()
+            .extend(path);
+
+        let request = self
+            .client
+            .get(url)
+            .header(ACCEPT, "application/vnd.github+json");
+
+        match &self.token {
+            Some(token) => request.header(AUTHORIZATION, format!("Bearer {}", token)),
+            None => request,
+        }
+    }
+
+    pub fn release(&self, repo: &str, tag: &str) -> Result<Release, GitHubError> {
+        let (owner, name) = split_repo(repo)?;
+        self.send_release(
+            self.request(&["repos", owner, name, "releases", "tags", tag]),
+            format!("{}@{}", repo, tag),
+        )
+    }
+
+    /// Downloads the content of a release asset.
+    pub fn download(&self, asset: &Asset) -> Result<Vec<u8>, GitHubError> {
+        Ok(self
+            .client
+            .get(&asset.browser_download_url)
+            .send()?
+            .error_for_status()?
+            .bytes()?
+            .to_vec())
+    }
+
+    /// Fetches the newest release that isn't a draft or a prerelease.
+    pub fn latest_release(&self, repo: &str) -> Result<Release, GitHubError> {
+        let (owner, name) = split_repo(repo)?;
+        self.send_release(
+            self.request(&["repos", owner, name, "releases", "latest"]),
+            format!("{}@latest", repo),
+        )
+    }
+
+    fn send_release(
+        &self,
+        request: RequestBuilder,
+        release: String,
+    ) -> Result<Release, GitHubError> {
+        let response = request.send()?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(GitHubError::Status { status, release });
+        }
+
+        Ok(response.json()?)
+    }
+}
@@ -0,0 +1,42 @@
+use sha1::Sha1;
+use sha2::{Digest, Sha512};
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+pub fn sha512(path: &Path) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut sha512 = Sha512::new();
+
+    io::copy(&mut file, &mut sha512)?;
+
+    Ok(hex::encode(sha512.finalize()))
+}
+
+pub fn sha1(path: &Path) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut sha1 = Sha1::new();
+
+    io::copy(&mut file, &mut sha1)?;
+
+    Ok(hex::encode(sha1.finalize()))
+}
+
+/// Compares a file against its known hashes, preferring sha512 over sha1.
+///
+/// Returns the algorithm, the expected and the actual hash if the file doesn't match.
+pub fn mismatch<'a>(
+    path: &Path,
+    sha512: Option<&'a str>,
+    sha1: Option<&'a str>,
+) -> io::Result<Option<(&'static str, &'a str, String)>> {
+    Ok(if let Some(expected) = sha512 {
+        let actual = self::sha512(path)?;
+        (actual != expected).then_some(("sha512", expected, actual))
+    } else if let Some(expected) = sha1 {
+        let actual = self::sha1(path)?;
+        (actual != expected).then_some(("sha1", expected, actual))
+    } else {
+        None
+    })
+}
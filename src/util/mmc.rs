@@ -0,0 +1,30 @@
+use serde::{Deserialize, Serialize};
+
+pub const PACK_FILE: &str = "mmc-pack.json";
+pub const MINECRAFT: &str = "net.minecraft";
+
+/// Folders holding the game files of an instance, depending on the launcher.
+pub const GAME_DIRS: [&str; 2] = [".minecraft", "minecraft"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Pack {
+    pub components: Vec<Component>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Component {
+    pub uid: String,
+    pub version: Option<String>,
+}
+
+pub fn component_loader(uid: &str) -> Option<&'static str> {
+    match uid {
+        "net.fabricmc.fabric-loader" => Some("fabric"),
+        "org.quiltmc.quilt-loader" => Some("quilt"),
+        "net.minecraftforge" => Some("forge"),
+        "net.neoforged" => Some("neoforge"),
+        _ => None,
+    }
+}
@@ -1 +1,7 @@
+pub mod download;
+pub mod github;
+pub mod hash;
+pub mod mmc;
 pub mod modrinth;
+pub mod mrpack;
+pub mod parallel;
@@ -13,6 +13,9 @@ pub enum ModrinthError {
 
     #[error("invalid slug or id `{0}`")]
     InvalidSlugOrId(String),
+
+    #[error("rate limited by modrinth, limit resets in {0} seconds")]
+    RateLimited(u64),
 }
 
 pub trait NotFound<T> {
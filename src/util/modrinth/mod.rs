@@ -1,31 +1,91 @@
 pub mod error;
 
+use chrono::{DateTime, Utc};
 use error::ModrinthError;
+use log::warn;
+use reqwest::blocking::{RequestBuilder, Response};
+use reqwest::header::AUTHORIZATION;
+use reqwest::{Method, StatusCode};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::env;
+use std::thread;
+use std::time::Duration;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Project {
     pub slug: String,
     pub id: String,
+    pub title: String,
+    pub description: String,
     pub project_type: ProjectType,
+    pub client_side: SideSupport,
+    pub server_side: SideSupport,
+    pub license: License,
+    pub source_url: Option<String>,
+    pub issues_url: Option<String>,
     pub versions: Vec<String>,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct License {
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
+pub enum SideSupport {
+    Required,
+    Optional,
+    Unsupported,
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
 pub enum ProjectType {
     Mod,
     Modpack,
     ResourcePack,
     Shader,
+    Datapack,
+    Plugin,
+    /// A type added to Modrinth since, kept as it was sent.
+    Unknown(String),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Version {
     pub id: String,
+    pub project_id: String,
     pub name: String,
     pub version_number: String,
+    pub date_published: DateTime<Utc>,
+    pub game_versions: Vec<String>,
+    pub loaders: Vec<String>,
     pub files: Vec<VersionFile>,
+    #[serde(default)]
+    pub dependencies: Vec<Dependency>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Dependency {
+    pub version_id: Option<String>,
+    pub project_id: Option<String>,
+    pub file_name: Option<String>,
+    pub dependency_type: DependencyType,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DependencyType {
+    Required,
+    Optional,
+    Incompatible,
+    Embedded,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -33,6 +93,7 @@ pub struct VersionFile {
     pub url: String,
     pub filename: String,
     pub primary: bool,
+    pub size: u64,
     pub hashes: Hashes,
 }
 
@@ -42,6 +103,66 @@ pub struct Hashes {
     pub sha1: String,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchResults {
+    pub hits: Vec<SearchHit>,
+    pub total_hits: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchHit {
+    pub project_id: String,
+    pub slug: String,
+    pub title: String,
+    pub description: String,
+    pub downloads: u64,
+}
+
+impl SideSupport {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SideSupport::Required => "required",
+            SideSupport::Optional => "optional",
+            SideSupport::Unsupported => "unsupported",
+            SideSupport::Unknown => "unknown",
+        }
+    }
+}
+
+impl ProjectType {
+    pub fn as_str(&self) -> &str {
+        match self {
+            ProjectType::Mod => "mod",
+            ProjectType::Modpack => "modpack",
+            ProjectType::ResourcePack => "resourcepack",
+            ProjectType::Shader => "shader",
+            ProjectType::Datapack => "datapack",
+            ProjectType::Plugin => "plugin",
+            ProjectType::Unknown(project_type) => project_type,
+        }
+    }
+}
+
+impl From<String> for ProjectType {
+    fn from(value: String) -> Self {
+        match value.as_str() {
+            "mod" => ProjectType::Mod,
+            "modpack" => ProjectType::Modpack,
+            "resourcepack" => ProjectType::ResourcePack,
+            "shader" => ProjectType::Shader,
+            "datapack" => ProjectType::Datapack,
+            "plugin" => ProjectType::Plugin,
+            _ => ProjectType::Unknown(value),
+        }
+    }
+}
+
+impl From<ProjectType> for String {
+    fn from(value: ProjectType) -> Self {
+        value.as_str().to_string()
+    }
+}
+
 impl Version {
     pub fn primary_file(&self) -> Option<&VersionFile> {
         self.files
@@ -51,14 +172,36 @@ impl Version {
     }
 }
 
+pub const DEFAULT_BASE_URL: &str = "https://api.modrinth.com/v2";
+pub const USER_AGENT: &str = concat!(
+    "niterpack/niterpack/",
+    env!("CARGO_PKG_VERSION"),
+    " (https://github.com/niterpack/niterpack)"
+);
+
+/// Remaining requests under which requests are slowed down.
+const RATE_LIMIT_THRESHOLD: u64 = 10;
+/// Seconds to wait when the rate limit reset isn't known.
+const RATE_LIMIT_RESET: u64 = 60;
+const RATE_LIMIT_RETRIES: u32 = 3;
+/// Maximum number of ids sent in a single bulk request, to keep urls short.
+const BULK_LIMIT: usize = 100;
+
+/// A client for the Modrinth API, sharing one connection pool between requests.
+#[derive(Debug, Clone)]
+pub struct ModrinthClient {
+    client: reqwest::blocking::Client,
+    base_url: String,
+    token: Option<String>,
+}
+
 macro_rules! get {
     (
+        $client:expr,
         path: [$( $path:expr ),+],
         $(query: { $($query:tt)* },)?
-    ) => {
-        let request = reqwest::blocking::Client::builder()
-            .build()?
-            .get(format!("https://api.modrinth.com/v2/{}", vec![$($path),*].join("/")));
+    ) => {{
+        let request = $client.request(Method::GET, &[$($path),*]);
 
         $(
         let mut query = Vec::new();
@@ -74,12 +217,16 @@ macro_rules! get {
         let request = request.query(&query);
         )?
 
-        let response = request.send()?;
+        $client.send(request)
+    }};
+}
 
-        Ok(serde_json::from_str(response.error_for_status()?
-            .text()?
-            .as_str())?)
-    };
+fn header(response: &Response, name: &str) -> Option<u64> {
+    response
+        .headers()
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse().ok())
 }
 
 pub fn check_slug(slug: &str) -> bool {
@@ -106,31 +253,199 @@ fn check_id_err(id: &str) -> Result<(), ModrinthError> {
     }
 }
 
-pub fn version(id: &str) -> Result<Version, ModrinthError> {
-    check_id_err(id)?;
-    get! {
-        path: ["version", id],
+impl ModrinthClient {
+    pub fn new(
+        base_url: String,
+        user_agent: &str,
+        token: Option<String>,
+    ) -> Result<Self, ModrinthError> {
+        Ok(Self {
+            client: reqwest::blocking::Client::builder()
+                .user_agent(user_agent)
+                .build()?,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            token,
+        })
     }
-}
 
-pub fn project(id: &str) -> Result<Project, ModrinthError> {
-    check_slug_err(id)?;
-    get! {
-        path: ["project", id],
+    /// Creates a client configured by the `NITER_MODRINTH_URL`, `NITER_MODRINTH_USER_AGENT`
+    /// and `NITER_MODRINTH_TOKEN` environment variables.
+    pub fn from_env() -> Result<Self, ModrinthError> {
+        Self::new(
+            env::var("NITER_MODRINTH_URL").unwrap_or_else(|_| DEFAULT_BASE_URL.to_string()),
+            &env::var("NITER_MODRINTH_USER_AGENT").unwrap_or_else(|_| USER_AGENT.to_string()),
+            env::var("NITER_MODRINTH_TOKEN").ok(),
+        )
     }
-}
 
-pub fn project_versions(
-    id: &str,
-    loader: Option<&str>,
-    game_version: Option<&str>,
-) -> Result<Vec<Version>, ModrinthError> {
-    check_slug_err(id)?;
-    get! {
-        path: ["project", id, "version"],
-        query: {
-            "loaders": loader.map(|loader| vec![loader]),
-            "game_versions": game_version.map(|game_version| vec![game_version]),
-        },
+    fn request(&self, method: Method, path: &[&str]) -> RequestBuilder {
+        let request = self
+            .client
+            .request(method, format!("{}/{}", self.base_url, path.join("/")));
+
+        match &self.token {
+            Some(token) => request.header(AUTHORIZATION, token),
+            None => request,
+        }
+    }
+
+    /// Sends a request, waiting for the rate limit to reset when it is exceeded.
+    fn send<T: DeserializeOwned>(&self, request: RequestBuilder) -> Result<T, ModrinthError> {
+        let mut retries = 0;
+
+        loop {
+            let response = request
+                .try_clone()
+                .expect("modrinth requests should not have a streaming body")
+                .send()?;
+
+            let remaining = header(&response, "X-Ratelimit-Remaining");
+            let reset = header(&response, "X-Ratelimit-Reset").unwrap_or(RATE_LIMIT_RESET);
+
+            if response.status() == StatusCode::TOO_MANY_REQUESTS {
+                if retries >= RATE_LIMIT_RETRIES {
+                    return Err(ModrinthError::RateLimited(reset));
+                }
+                retries += 1;
+
+                warn!("rate limited by modrinth, waiting {} seconds", reset);
+                thread::sleep(Duration::from_secs(reset));
+                continue;
+            }
+
+            // Spread the remaining requests over the time until the limit resets
+            if let Some(remaining) = remaining.filter(|remaining| *remaining < RATE_LIMIT_THRESHOLD)
+            {
+                thread::sleep(Duration::from_secs(reset) / (remaining as u32 + 1));
+            }
+
+            return Ok(serde_json::from_str(
+                response.error_for_status()?.text()?.as_str(),
+            )?);
+        }
+    }
+
+    pub fn version(&self, id: &str) -> Result<Version, ModrinthError> {
+        check_id_err(id)?;
+        get! {
+            self,
+            path: ["version", id],
+        }
+    }
+
+    pub fn version_file(&self, hash: &str, algorithm: &str) -> Result<Version, ModrinthError> {
+        get! {
+            self,
+            path: ["version_file", hash],
+            query: {
+                "algorithm": algorithm,
+            },
+        }
+    }
+
+    /// Fetches multiple versions by their ids, in as few requests as possible.
+    ///
+    /// Ids that don't belong to any version are left out of the result.
+    pub fn versions(&self, ids: &[&str]) -> Result<Vec<Version>, ModrinthError> {
+        let mut versions = Vec::new();
+        for ids in ids.chunks(BULK_LIMIT) {
+            for id in ids {
+                check_id_err(id)?;
+            }
+
+            let chunk: Vec<Version> = get! {
+                self,
+                path: ["versions"],
+                query: {
+                    "ids": ids,
+                },
+            }?;
+            versions.extend(chunk);
+        }
+        Ok(versions)
+    }
+
+    /// Fetches the versions containing files with the given hashes, in a single request.
+    ///
+    /// Hashes that don't match any file are left out of the result.
+    pub fn version_files(
+        &self,
+        hashes: &[&str],
+        algorithm: &str,
+    ) -> Result<HashMap<String, Version>, ModrinthError> {
+        self.send(
+            self.request(Method::POST, &["version_files"])
+                .json(&serde_json::json!({
+                    "hashes": hashes,
+                    "algorithm": algorithm,
+                })),
+        )
+    }
+
+    pub fn project(&self, id: &str) -> Result<Project, ModrinthError> {
+        check_slug_err(id)?;
+        get! {
+            self,
+            path: ["project", id],
+        }
+    }
+
+    /// Fetches multiple projects by their slugs or ids, in as few requests as possible.
+    ///
+    /// Projects that don't exist are left out of the result.
+    pub fn projects(&self, ids: &[&str]) -> Result<Vec<Project>, ModrinthError> {
+        let mut projects = Vec::new();
+        for ids in ids.chunks(BULK_LIMIT) {
+            for id in ids {
+                check_slug_err(id)?;
+            }
+
+            let chunk: Vec<Project> = get! {
+                self,
+                path: ["projects"],
+                query: {
+                    "ids": ids,
+                },
+            }?;
+            projects.extend(chunk);
+        }
+        Ok(projects)
+    }
+
+    pub fn project_versions(
+        &self,
+        id: &str,
+        loaders: Option<&[&str]>,
+        game_version: Option<&str>,
+    ) -> Result<Vec<Version>, ModrinthError> {
+        check_slug_err(id)?;
+        get! {
+            self,
+            path: ["project", id, "version"],
+            query: {
+                "loaders": loaders,
+                "game_versions": game_version.map(|game_version| vec![game_version]),
+            },
+        }
+    }
+
+    /// Searches projects matching a query.
+    ///
+    /// Each inner list of `facets` is combined using OR, and the lists are combined using AND.
+    pub fn search(
+        &self,
+        query: &str,
+        facets: &[Vec<String>],
+        limit: usize,
+    ) -> Result<SearchResults, ModrinthError> {
+        get! {
+            self,
+            path: ["search"],
+            query: {
+                "query": query,
+                "facets": (!facets.is_empty()).then_some(facets),
+                "limit": limit,
+            },
+        }
     }
 }
@@ -0,0 +1,69 @@
+use crate::util::modrinth::SideSupport;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+pub const INDEX_FILE: &str = "modrinth.index.json";
+pub const OVERRIDES_DIR: &str = "overrides";
+pub const CLIENT_OVERRIDES_DIR: &str = "client-overrides";
+pub const SERVER_OVERRIDES_DIR: &str = "server-overrides";
+pub const MODRINTH_CDN: &str = "cdn.modrinth.com";
+pub const FORMAT_VERSION: u32 = 1;
+pub const GAME: &str = "minecraft";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Index {
+    pub format_version: u32,
+    pub game: String,
+    pub version_id: String,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    pub files: Vec<IndexFile>,
+    pub dependencies: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexFile {
+    pub path: String,
+    pub hashes: IndexHashes,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub env: Option<IndexEnv>,
+    pub downloads: Vec<String>,
+    pub file_size: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexHashes {
+    pub sha1: String,
+    pub sha512: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexEnv {
+    pub client: SideSupport,
+    pub server: SideSupport,
+}
+
+/// Returns the `dependencies` key used for a mod loader.
+pub fn loader_dependency(loader: &str) -> Option<&'static str> {
+    match loader {
+        "fabric" => Some("fabric-loader"),
+        "quilt" => Some("quilt-loader"),
+        "forge" => Some("forge"),
+        "neoforge" => Some("neoforge"),
+        _ => None,
+    }
+}
+
+/// Returns the mod loader for a `dependencies` key.
+pub fn dependency_loader(key: &str) -> Option<&'static str> {
+    match key {
+        "fabric-loader" => Some("fabric"),
+        "quilt-loader" => Some("quilt"),
+        "forge" => Some("forge"),
+        "neoforge" => Some("neoforge"),
+        _ => None,
+    }
+}